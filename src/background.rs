@@ -0,0 +1,286 @@
+//! The background thread that writes the log files, see `Logger::set_async`.
+
+use std::collections::VecDeque;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, JoinHandle};
+
+use crate::file::{FileTarget, WriteOptions};
+
+/// What a log call does when the queue of an async `Logger` is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Wait for the background thread to make room, so no line is lost.
+    #[default]
+    Block,
+    /// Drop the oldest queued line to make room, counted by `Logger::dropped_count`.
+    DropOldest,
+}
+
+/// A line waiting to be written.
+struct Job {
+    entry: Vec<u8>,
+    targets: Vec<Arc<FileTarget>>,
+    options: WriteOptions,
+}
+
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<State>,
+    /// Notified whenever `State` changes.
+    changed: Condvar,
+    capacity: usize,
+    policy: OverflowPolicy,
+    dropped: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    queue: VecDeque<Job>,
+    /// Queued lines, and the one being written.
+    unwritten: usize,
+    closed: bool,
+    /// Whether the thread is gone, after the queue was closed or because it panicked.
+    stopped: bool,
+}
+
+impl std::fmt::Debug for Job {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Job")
+            .field("len", &self.entry.len())
+            .finish()
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn wait<'a>(&self, state: MutexGuard<'a, State>) -> MutexGuard<'a, State> {
+        self.changed
+            .wait(state)
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn push(&self, job: Job) -> io::Result<()> {
+        let mut state = self.lock();
+
+        loop {
+            // Nothing would write the line.
+            if state.stopped {
+                return Err(stopped());
+            }
+            if state.queue.len() < self.capacity {
+                break;
+            }
+
+            match self.policy {
+                OverflowPolicy::Block => state = self.wait(state),
+                OverflowPolicy::DropOldest => {
+                    state.queue.pop_front();
+                    state.unwritten -= 1;
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+
+        state.queue.push_back(job);
+        state.unwritten += 1;
+        self.changed.notify_all();
+
+        Ok(())
+    }
+
+    /// Writes queued lines until the queue is closed and empty.
+    fn run(&self) {
+        let _stopped = Stopped(self);
+
+        loop {
+            let job = {
+                let mut state = self.lock();
+
+                loop {
+                    match state.queue.pop_front() {
+                        Some(job) => break job,
+                        None if state.closed => return,
+                        None => state = self.wait(state),
+                    }
+                }
+            };
+            self.changed.notify_all();
+
+            for target in &job.targets {
+                target.write_or_defer(&job.entry, &job.options);
+            }
+
+            self.lock().unwritten -= 1;
+            self.changed.notify_all();
+        }
+    }
+}
+
+/// Marks the thread as gone when it returns or unwinds, so nothing waits for it forever.
+struct Stopped<'a>(&'a Shared);
+
+impl Drop for Stopped<'_> {
+    fn drop(&mut self) {
+        self.0.lock().stopped = true;
+        self.0.changed.notify_all();
+    }
+}
+
+fn stopped() -> io::Error {
+    io::Error::other("the background writer thread stopped")
+}
+
+/// The queue of an async `Logger` and its thread, which is stopped once the queue is drained
+/// when the last `Logger` using it lets go.
+#[derive(Debug)]
+pub(crate) struct Writer {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Writer {
+    /// Starts a thread writing the lines pushed onto a queue of `capacity` lines.
+    pub(crate) fn spawn(capacity: usize, policy: OverflowPolicy) -> io::Result<Self> {
+        let shared = Arc::new(Shared {
+            state: Mutex::default(),
+            changed: Condvar::new(),
+            capacity: capacity.max(1),
+            policy,
+            dropped: AtomicU64::new(0),
+        });
+
+        let thread = thread::Builder::new().name("lawg".to_string()).spawn({
+            let shared = Arc::clone(&shared);
+            move || shared.run()
+        })?;
+
+        Ok(Writer {
+            shared,
+            thread: Some(thread),
+        })
+    }
+
+    /// Queues `entry` to be written to `targets`, failing if the thread is gone.
+    pub(crate) fn push(
+        &self,
+        entry: &[u8],
+        targets: Vec<Arc<FileTarget>>,
+        options: WriteOptions,
+    ) -> io::Result<()> {
+        self.shared.push(Job {
+            entry: entry.to_vec(),
+            targets,
+            options,
+        })
+    }
+
+    /// Waits until every line queued so far is written, failing if the thread is gone before that.
+    pub(crate) fn wait_until_written(&self) -> io::Result<()> {
+        let mut state = self.shared.lock();
+
+        while state.unwritten > 0 {
+            if state.stopped {
+                return Err(stopped());
+            }
+            state = self.shared.wait(state);
+        }
+
+        Ok(())
+    }
+
+    /// How many lines `OverflowPolicy::DropOldest` dropped.
+    pub(crate) fn dropped(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Writes what's still queued, then stops the thread.
+impl Drop for Writer {
+    fn drop(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.changed.notify_all();
+
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Job, OverflowPolicy, Shared, Writer};
+    use crate::file::{FileTarget, FlushPolicy, WriteOptions};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Condvar, Mutex};
+
+    fn options() -> WriteOptions {
+        WriteOptions {
+            rotation: None,
+            max_backups: None,
+            append_only: false,
+            flush_policy: FlushPolicy::EveryLine,
+            use_utc: true,
+        }
+    }
+
+    #[test]
+    fn drop_oldest_keeps_the_newest_lines() {
+        // No thread writes these, so the queue stays full.
+        let shared = Shared {
+            state: Mutex::default(),
+            changed: Condvar::new(),
+            capacity: 2,
+            policy: OverflowPolicy::DropOldest,
+            dropped: AtomicU64::new(0),
+        };
+
+        for i in 0..5 {
+            shared
+                .push(Job {
+                    entry: vec![i],
+                    targets: Vec::new(),
+                    options: options(),
+                })
+                .unwrap();
+        }
+
+        let state = shared.lock();
+        let queued: Vec<u8> = state.queue.iter().map(|job| job.entry[0]).collect();
+        assert_eq!(queued, [3, 4]);
+        assert_eq!(state.unwritten, 2);
+        assert_eq!(shared.dropped.load(Ordering::Relaxed), 3);
+    }
+
+    /// A sink that panics, like a write error escalating to a panic would.
+    struct Panicking;
+
+    impl std::io::Write for Panicking {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            panic!("the sink broke");
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn waiting_on_a_panicked_thread_fails() {
+        let writer = Writer::spawn(1, OverflowPolicy::Block).unwrap();
+        let target = Arc::new(FileTarget::with_writer(Panicking, 0));
+
+        writer
+            .push(b"\nline", vec![target.clone()], options())
+            .unwrap();
+
+        assert!(writer.wait_until_written().is_err());
+        assert!(writer
+            .push(b"\nline", vec![target.clone()], options())
+            .is_err());
+    }
+}
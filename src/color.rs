@@ -0,0 +1,46 @@
+//! ANSI colors for console lines, see `Logger.color_mode`.
+
+use std::fmt::{self, Write};
+
+use crate::Level;
+
+/// Whether console lines are colored. Log files are never colored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorMode {
+    /// Always color, even when the console isn't a terminal.
+    Always,
+    /// Never color.
+    #[default]
+    Never,
+    /// Color when the line's stream is a terminal and the `NO_COLOR` environment variable isn't set.
+    /// Writers set with `Logger::set_console_writers` are never colored.
+    Auto,
+}
+
+pub(crate) const RED: &str = "\x1b[31m";
+pub(crate) const YELLOW: &str = "\x1b[33m";
+pub(crate) const BOLD: &str = "\x1b[1m";
+pub(crate) const RESET: &str = "\x1b[0m";
+
+/// The color of a level's name, if it has one.
+pub(crate) fn of_level(level: Level) -> Option<&'static str> {
+    match level {
+        Level::Error => Some(RED),
+        Level::Warn => Some(YELLOW),
+        _ => None,
+    }
+}
+
+/// Writes `text` to `line`, in `color` if there is one.
+pub(crate) fn paint<T: fmt::Display>(line: &mut String, color: Option<&str>, text: T) {
+    match color {
+        Some(color) => write!(line, "{}{}{}", color, text, RESET),
+        None => write!(line, "{}", text),
+    }
+    .unwrap();
+}
+
+/// Whether `NO_COLOR` is set to something, see <https://no-color.org>.
+pub(crate) fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
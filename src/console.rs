@@ -0,0 +1,196 @@
+//! Where console lines go, and coordination of console writes with other things drawing on the terminal.
+
+use std::cell::Cell;
+use std::fmt;
+use std::io::{self, IsTerminal, Write};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+
+use crate::{color, Level};
+
+/// Which stream console lines are written to, see `Logger.console_target`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConsoleTarget {
+    /// Everything goes to stdout.
+    Stdout,
+    /// Everything goes to stderr.
+    Stderr,
+    /// Errors go to stderr and everything else to stdout.
+    #[default]
+    Auto,
+}
+
+impl ConsoleTarget {
+    /// Whether lines of `level` go to stderr.
+    pub(crate) fn is_stderr(self, level: Level) -> bool {
+        match self {
+            ConsoleTarget::Stdout => false,
+            ConsoleTarget::Stderr => true,
+            ConsoleTarget::Auto => level == Level::Error,
+        }
+    }
+}
+
+/// A stream console lines are written to.
+#[derive(Clone)]
+pub(crate) enum Stream {
+    Stdout,
+    Stderr,
+    /// Set with `Logger::set_console_writers`, and shared with the `Logger`'s children.
+    Writer(Arc<Mutex<Box<dyn Write + Send>>>),
+}
+
+impl Stream {
+    /// Writes `line` whole, holding the stream's lock.
+    pub(crate) fn write_line(&self, line: &[u8]) -> io::Result<()> {
+        match self {
+            Stream::Stdout => io::stdout().lock().write_all(line),
+            Stream::Stderr => io::stderr().lock().write_all(line),
+            Stream::Writer(writer) => {
+                let mut writer = writer.lock().unwrap_or_else(PoisonError::into_inner);
+                writer.write_all(line)?;
+                writer.flush()
+            }
+        }
+    }
+
+    /// Whether `ColorMode::Auto` colors lines written to this stream.
+    pub(crate) fn auto_colors(&self) -> bool {
+        static STDOUT: OnceLock<bool> = OnceLock::new();
+        static STDERR: OnceLock<bool> = OnceLock::new();
+
+        match self {
+            Stream::Stdout => {
+                *STDOUT.get_or_init(|| !color::no_color() && io::stdout().is_terminal())
+            }
+            Stream::Stderr => {
+                *STDERR.get_or_init(|| !color::no_color() && io::stderr().is_terminal())
+            }
+            Stream::Writer(_) => false,
+        }
+    }
+
+    /// Flushes what was written so far.
+    pub(crate) fn flush(&self) -> io::Result<()> {
+        match self {
+            Stream::Stdout => io::stdout().flush(),
+            Stream::Stderr => io::stderr().flush(),
+            Stream::Writer(writer) => writer
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .flush(),
+        }
+    }
+}
+
+impl fmt::Debug for Stream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stream::Stdout => f.write_str("Stdout"),
+            Stream::Stderr => f.write_str("Stderr"),
+            Stream::Writer(_) => f.write_str("Writer"),
+        }
+    }
+}
+
+/// Runs around every console write of a `Logger`, e.g. to hide a progress bar while the line is printed.
+///
+/// Implement `before_write` and `after_write`, or override `around_write` for APIs that
+/// take a closure, like indicatif's `MultiProgress::suspend`.
+/// Records that are only written to a file never reach the coordinator.
+///
+/// Console writes made from inside a coordinator (on the same thread) skip coordination,
+/// so a coordinator may log without recursing or deadlocking.
+/// # Example
+/// ```rust
+/// use lawg::{ConsoleCoordinator, Logger};
+///
+/// struct ClearLine;
+///
+/// impl ConsoleCoordinator for ClearLine {
+///     fn before_write(&self) {
+///         print!("\r\x1b[2K");
+///     }
+/// }
+///
+/// let mut my_logger = Logger::new(String::from("My Logger"), None, true);
+/// my_logger.set_console_coordinator(ClearLine);
+/// my_logger.log("Drawn on a clean line");
+/// ```
+pub trait ConsoleCoordinator: Send + Sync {
+    /// Called right before a line is written to the console.
+    fn before_write(&self) {}
+
+    /// Called right after a line is written to the console.
+    fn after_write(&self) {}
+
+    /// Performs the console write, which must be done by calling `write` exactly once.
+    fn around_write(&self, write: &mut dyn FnMut()) {
+        self.before_write();
+        write();
+        self.after_write();
+    }
+}
+
+impl<T: ConsoleCoordinator + ?Sized> ConsoleCoordinator for Arc<T> {
+    fn before_write(&self) {
+        (**self).before_write();
+    }
+
+    fn after_write(&self) {
+        (**self).after_write();
+    }
+
+    fn around_write(&self, write: &mut dyn FnMut()) {
+        (**self).around_write(write);
+    }
+}
+
+/// Suspends the progress bars while the line is written.
+#[cfg(feature = "indicatif")]
+impl ConsoleCoordinator for indicatif::MultiProgress {
+    fn around_write(&self, write: &mut dyn FnMut()) {
+        self.suspend(write);
+    }
+}
+
+/// Suspends the progress bar while the line is written.
+#[cfg(feature = "indicatif")]
+impl ConsoleCoordinator for indicatif::ProgressBar {
+    fn around_write(&self, write: &mut dyn FnMut()) {
+        self.suspend(write);
+    }
+}
+
+/// The coordinator set on a `Logger`.
+#[derive(Clone)]
+pub(crate) struct Coordinator(pub(crate) Arc<dyn ConsoleCoordinator>);
+
+impl fmt::Debug for Coordinator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Coordinator")
+    }
+}
+
+thread_local! {
+    /// Whether this thread is inside a coordinator, so nested console writes skip it.
+    static COORDINATING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Resets `COORDINATING` when the coordinator returns, even by panicking.
+struct Coordinating;
+
+impl Drop for Coordinating {
+    fn drop(&mut self) {
+        COORDINATING.with(|coordinating| coordinating.set(false));
+    }
+}
+
+/// Runs `write` through `coordinator`, unless this thread is already inside one.
+pub(crate) fn coordinate(coordinator: &dyn ConsoleCoordinator, write: &mut dyn FnMut()) {
+    if COORDINATING.with(|coordinating| coordinating.replace(true)) {
+        write();
+    } else {
+        let _coordinating = Coordinating;
+        coordinator.around_write(write);
+    }
+}
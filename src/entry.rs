@@ -0,0 +1,149 @@
+//! Reading log files back, see `Logger::read_entries`.
+
+use chrono::{DateTime, FixedOffset, NaiveDateTime, Utc};
+
+use crate::Level;
+
+/// A log line read back from a log file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    pub timestamp: DateTime<Utc>,
+    /// `Level::Info` for lines without a level, like the ones written by `Logger::log_to_file`.
+    pub level: Level,
+    pub logger_name: String,
+    /// The message, with the extra fields appended as `key=value` if there were any.
+    pub message: String,
+}
+
+/// How a line of a log file starts.
+enum Line {
+    Entry(LogEntry),
+    /// The rest of the previous entry's message.
+    Continued,
+    /// A header that can't be read, with the reason.
+    Corrupt(&'static str),
+}
+
+/// Parses log lines in the default layout,
+/// `[LEVEL: ]name - ["yyyy-mm-dd hh:mm:ss.f UTC"]: message`, into entries.
+///
+/// A line that doesn't start like that belongs to the previous entry's message.
+/// `corrupt` is called with the line number and the reason for each line that starts like that
+/// but can't be read, and for lines before the first entry, and the line is skipped.
+pub(crate) fn parse(content: &str, mut corrupt: impl FnMut(usize, &str)) -> Vec<LogEntry> {
+    let mut entries: Vec<LogEntry> = Vec::new();
+
+    for (i, line) in content.lines().enumerate() {
+        match parse_header(line) {
+            Line::Entry(entry) => entries.push(entry),
+            Line::Continued => match entries.last_mut() {
+                Some(entry) => {
+                    entry.message.push('\n');
+                    entry.message.push_str(line);
+                }
+                // Every entry starts with a newline, so files start with an empty line.
+                None if line.is_empty() => {}
+                None => corrupt(i + 1, "not part of an entry"),
+            },
+            Line::Corrupt(reason) => corrupt(i + 1, reason),
+        }
+    }
+
+    entries
+}
+
+fn parse_header(line: &str) -> Line {
+    let Some((head, rest)) = line.split_once(" - [\"") else {
+        return Line::Continued;
+    };
+    let Some((timestamp, message)) = rest.split_once("\"]: ") else {
+        return Line::Continued;
+    };
+
+    let Some(timestamp) = parse_timestamp(timestamp) else {
+        return Line::Corrupt("invalid timestamp");
+    };
+
+    let (level, logger_name) = (0..=4)
+        .map(Level::from_u8)
+        .find_map(|level| Some((level, head.strip_prefix(level.prefix())?)))
+        .unwrap_or((Level::Info, head));
+
+    if logger_name.is_empty() {
+        return Line::Corrupt("missing logger name");
+    }
+
+    Line::Entry(LogEntry {
+        timestamp,
+        level,
+        logger_name: logger_name.to_string(),
+        message: message.to_string(),
+    })
+}
+
+/// Parses a timestamp rendered by `DateTime<Utc>` or `DateTime<Local>`'s `Display`,
+/// like `2021-11-08 10:59:32.007546200 UTC` or `2021-11-08 12:59:32.007546200 +02:00`.
+fn parse_timestamp(timestamp: &str) -> Option<DateTime<Utc>> {
+    match timestamp.strip_suffix(" UTC") {
+        Some(utc) => NaiveDateTime::parse_from_str(utc, "%Y-%m-%d %H:%M:%S%.f")
+            .ok()
+            .map(|time| time.and_utc()),
+        None => DateTime::<FixedOffset>::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S%.f %:z")
+            .ok()
+            .map(|time| time.with_timezone(&Utc)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+    use crate::Level;
+
+    #[test]
+    fn parses_levels_continuations_and_corrupt_lines() {
+        let content = "\nstray\nWARN: app - [\"2021-11-08 10:59:32.007546200 UTC\"]: Low\
+            \napp::db - [\"2021-11-08 12:59:33 +02:00\"]: Two\n\nlines\
+            \nERROR: app - [\"yesterday\"]: Gone";
+        let mut corrupt = Vec::new();
+
+        let entries = parse(content, |line, reason| {
+            corrupt.push((line, reason.to_string()))
+        });
+
+        let read: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.timestamp.to_rfc3339(),
+                    entry.level,
+                    entry.logger_name.as_str(),
+                    entry.message.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            read,
+            [
+                (
+                    "2021-11-08T10:59:32.007546200+00:00".to_string(),
+                    Level::Warn,
+                    "app",
+                    "Low"
+                ),
+                (
+                    "2021-11-08T10:59:33+00:00".to_string(),
+                    Level::Info,
+                    "app::db",
+                    "Two\n\nlines"
+                ),
+            ]
+        );
+        assert_eq!(
+            corrupt,
+            [
+                (2, "not part of an entry".to_string()),
+                (7, "invalid timestamp".to_string())
+            ]
+        );
+    }
+}
@@ -0,0 +1,55 @@
+//! The error type returned by the fallible `Logger` methods.
+
+use std::fmt;
+
+/// An error from one of the `try_` methods of `Logger`.
+#[derive(Debug)]
+pub enum LoggerError {
+    /// Reading, creating or writing the log file or the console failed.
+    Io(std::io::Error),
+    /// A file method was called on a `Logger` without a log file.
+    NoLogFile,
+    /// Local time was asked for, but it needs the `chrono` feature.
+    LocalTimeUnavailable,
+    /// A line or time format passed to `Logger::set_format` or `Logger::set_time_format` is invalid,
+    /// or `Logger::read_entries` can't read lines in the logger's format.
+    InvalidFormat(String),
+    /// `lawg::init` was called after the global logger was already set.
+    AlreadyInitialized,
+    /// `Logger.rotation` is set on an append-only logger, whose log file only ever grows,
+    /// so it can't be rotated away or pruned.
+    AppendOnly,
+}
+
+impl fmt::Display for LoggerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoggerError::Io(err) => write!(f, "{}", err),
+            LoggerError::NoLogFile => write!(f, "Log file not provided."),
+            LoggerError::LocalTimeUnavailable => write!(
+                f,
+                "Local time is unavailable without the `chrono` feature, use `use_utc = true`"
+            ),
+            LoggerError::InvalidFormat(reason) => write!(f, "Invalid format: {}", reason),
+            LoggerError::AlreadyInitialized => write!(f, "The global logger is already set."),
+            LoggerError::AppendOnly => {
+                write!(f, "Append-only log files can't be rotated or pruned.")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoggerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoggerError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for LoggerError {
+    fn from(err: std::io::Error) -> Self {
+        LoggerError::Io(err)
+    }
+}
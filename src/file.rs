@@ -0,0 +1,374 @@
+//! The log files a `Logger` keeps open.
+
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::SystemTime;
+
+use crate::rotation::{self, RotationPolicy};
+use crate::{time, Level, LoggerError};
+
+/// When lines written to the log files are flushed from the buffer to the OS, see `Logger.flush_policy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlushPolicy {
+    /// After every line, so nothing is lost if the process crashes.
+    #[default]
+    EveryLine,
+    /// After every this many lines, and whenever the buffer is full.
+    EveryN(usize),
+    /// Only when the buffer is full, on `Logger::flush`, and when the `Logger` is dropped or stops the program.
+    Manual,
+}
+
+/// The `Logger` settings that apply to writing a line to a file.
+pub(crate) struct WriteOptions {
+    pub(crate) rotation: Option<RotationPolicy>,
+    pub(crate) max_backups: Option<usize>,
+    pub(crate) append_only: bool,
+    pub(crate) flush_policy: FlushPolicy,
+    pub(crate) use_utc: bool,
+}
+
+/// A log file added with `Logger::add_file_target`, or made from `Logger.file_log`.
+#[derive(Debug)]
+pub(crate) struct FileTarget {
+    pub(crate) name: String,
+    pub(crate) path: String,
+    /// The least important level written to this file.
+    pub(crate) min_level: Level,
+    file: Mutex<LogFile>,
+    /// The first error from `FileTarget::write_or_defer`, until `FileTarget::flush` returns it.
+    deferred: Mutex<Option<io::Error>>,
+}
+
+impl FileTarget {
+    /// Opens the log file `path` for appending, creating it and its parent directories if they don't exist,
+    /// with a buffer of `buffer_size` bytes.
+    pub(crate) fn open(
+        name: &str,
+        path: &str,
+        min_level: Level,
+        buffer_size: usize,
+        use_utc: bool,
+    ) -> io::Result<Self> {
+        // A bare file name has an empty parent, the current directory, which exists.
+        if let Some(parent) = Path::new(path).parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+
+        Ok(FileTarget {
+            name: name.to_string(),
+            path: path.to_string(),
+            min_level,
+            file: Mutex::new(LogFile::open(path, buffer_size, use_utc)?),
+            deferred: Mutex::new(None),
+        })
+    }
+
+    /// A target writing to `writer` instead of a file.
+    #[cfg(test)]
+    pub(crate) fn with_writer(writer: impl Write + Send + 'static, buffer_size: usize) -> Self {
+        FileTarget {
+            name: "default".to_string(),
+            path: "unused.txt".to_string(),
+            min_level: Level::Trace,
+            file: Mutex::new(LogFile::with_writer(writer, buffer_size)),
+            deferred: Mutex::new(None),
+        }
+    }
+
+    /// Appends `entry`, rotating the file first if `options.rotation` says so.
+    /// With `options.append_only`, the entry is written with a single `write` call, ignoring the flush policy.
+    pub(crate) fn write(&self, entry: &[u8], options: &WriteOptions) -> io::Result<()> {
+        let mut file = self.lock();
+
+        if let Some(policy) = options.rotation {
+            file.rotate_before(
+                &self.path,
+                policy,
+                options.max_backups,
+                entry.len(),
+                options.use_utc,
+            )?;
+        }
+
+        if options.append_only {
+            file.flush()?;
+            write_once(file.writer.get_mut(), entry)?;
+        } else {
+            file.writer.write_all(entry)?;
+            file.unflushed += 1;
+
+            let due = match options.flush_policy {
+                FlushPolicy::EveryLine => true,
+                FlushPolicy::EveryN(lines) => file.unflushed >= lines,
+                FlushPolicy::Manual => false,
+            };
+
+            if due {
+                file.flush()?;
+            }
+        }
+        file.size += entry.len() as u64;
+
+        Ok(())
+    }
+
+    /// Like `FileTarget::write`, but an error is kept for the next `FileTarget::flush` to return,
+    /// for writes no caller waits for.
+    pub(crate) fn write_or_defer(&self, entry: &[u8], options: &WriteOptions) {
+        if let Err(err) = self.write(entry, options) {
+            self.deferred
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get_or_insert(err);
+        }
+    }
+
+    /// Flushes the buffered lines to the OS, or returns the error `FileTarget::write_or_defer` kept.
+    pub(crate) fn flush(&self) -> io::Result<()> {
+        let deferred = self
+            .deferred
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+
+        let flushed = self.lock().flush();
+
+        match deferred {
+            Some(err) => Err(err),
+            None => flushed,
+        }
+    }
+
+    /// Flushes the buffered lines, then continues with a buffer of `buffer_size` bytes.
+    pub(crate) fn set_buffer_size(&self, buffer_size: usize, use_utc: bool) -> io::Result<()> {
+        let mut file = self.lock();
+
+        file.flush()?;
+        *file = LogFile::open(&self.path, buffer_size, use_utc)?;
+
+        Ok(())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, LogFile> {
+        self.file.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Writes `entry` with a single `write` call, failing if only part of it was written.
+fn write_once<W: Write + ?Sized>(writer: &mut W, entry: &[u8]) -> io::Result<()> {
+    loop {
+        match writer.write(entry) {
+            Ok(written) if written == entry.len() => return Ok(()),
+            Ok(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "entry was only partly written",
+                ))
+            }
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// An error from writing to the log files, with the path of the file it came from, if any.
+pub(crate) struct FileError<'a> {
+    pub(crate) err: LoggerError,
+    pub(crate) path: Option<&'a str>,
+}
+
+impl From<FileError<'_>> for LoggerError {
+    fn from(err: FileError<'_>) -> Self {
+        err.err
+    }
+}
+
+/// An open log file, with what rotation needs to know about it.
+struct LogFile {
+    /// The file, or another sink in tests.
+    writer: BufWriter<Box<dyn Write + Send>>,
+    /// Lines written since the last flush.
+    unflushed: usize,
+    /// The file's size, counted as entries are written, so it's never read again.
+    size: u64,
+    /// The day the file was last written on.
+    date: (i64, u32, u32),
+}
+
+impl std::fmt::Debug for LogFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogFile")
+            .field("unflushed", &self.unflushed)
+            .field("size", &self.size)
+            .field("date", &self.date)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LogFile {
+    /// Opens the log file `path` for appending, creating it if it doesn't exist.
+    fn open(path: &str, buffer_size: usize, use_utc: bool) -> io::Result<Self> {
+        let file = fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)?;
+        let metadata = file.metadata()?;
+
+        // An existing file carries on the day it was last written.
+        let written = match metadata.len() {
+            0 => SystemTime::now(),
+            _ => metadata.modified().unwrap_or_else(|_| SystemTime::now()),
+        };
+
+        Ok(LogFile {
+            writer: BufWriter::with_capacity(buffer_size, Box::new(file)),
+            unflushed: 0,
+            size: metadata.len(),
+            date: time::date(written, use_utc),
+        })
+    }
+
+    /// An empty log file writing to `writer` instead of a file, which rotation would replace with the file.
+    #[cfg(test)]
+    fn with_writer(writer: impl Write + Send + 'static, buffer_size: usize) -> Self {
+        LogFile {
+            writer: BufWriter::with_capacity(buffer_size, Box::new(writer)),
+            unflushed: 0,
+            size: 0,
+            date: time::date(SystemTime::now(), true),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.unflushed = 0;
+        self.writer.flush()
+    }
+
+    /// Rotates the file `path` first if writing `len` more bytes now would break `policy`.
+    fn rotate_before(
+        &mut self,
+        path: &str,
+        policy: RotationPolicy,
+        max_backups: Option<usize>,
+        len: usize,
+        use_utc: bool,
+    ) -> io::Result<()> {
+        let today = match policy {
+            RotationPolicy::Daily => Some(time::date(SystemTime::now(), use_utc)),
+            RotationPolicy::MaxSize(_) => None,
+        };
+
+        let due = self.size > 0
+            && match policy {
+                RotationPolicy::MaxSize(max) => self.size + len as u64 > max,
+                RotationPolicy::Daily => today != Some(self.date),
+            };
+
+        if due {
+            self.flush()?;
+            rotation::rotate(path, policy, max_backups, self.date)?;
+            *self = LogFile::open(path, self.writer.capacity(), use_utc)?;
+        }
+
+        if let Some(today) = today {
+            self.date = today;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FileTarget, FlushPolicy, WriteOptions};
+    use std::sync::{Arc, Mutex};
+
+    /// A sink recording the length of every `write` call, accepting at most `limit` bytes per call.
+    #[derive(Clone)]
+    struct CountingWriter {
+        writes: Arc<Mutex<Vec<usize>>>,
+        limit: usize,
+    }
+
+    impl std::io::Write for CountingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.writes.lock().unwrap().push(buf.len());
+            Ok(buf.len().min(self.limit))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn options(append_only: bool) -> WriteOptions {
+        WriteOptions {
+            rotation: None,
+            max_backups: None,
+            append_only,
+            // Even a policy that would buffer lines doesn't apply to append-only writes.
+            flush_policy: FlushPolicy::Manual,
+            use_utc: true,
+        }
+    }
+
+    #[test]
+    fn append_only_entries_are_written_once() {
+        let writer = CountingWriter {
+            writes: Arc::default(),
+            limit: usize::MAX,
+        };
+        let target = FileTarget::with_writer(writer.clone(), 64);
+        let entries = [
+            &b"\nshort"[..],
+            &[b'x'; 100_000],
+            b"\nlonger than the buffer...........................................",
+            b"\n",
+        ];
+
+        for entry in entries {
+            target.write(entry, &options(true)).unwrap();
+        }
+        target.flush().unwrap();
+
+        let lengths: Vec<usize> = entries.iter().map(|entry| entry.len()).collect();
+        assert_eq!(*writer.writes.lock().unwrap(), lengths);
+    }
+
+    #[test]
+    fn buffered_entries_are_not_written_once() {
+        let writer = CountingWriter {
+            writes: Arc::default(),
+            limit: usize::MAX,
+        };
+        let target = FileTarget::with_writer(writer.clone(), 64);
+
+        // Without append-only, short entries wait in the buffer and reach the sink together.
+        for _ in 0..4 {
+            target.write(b"\nshort", &options(false)).unwrap();
+        }
+        target.flush().unwrap();
+
+        assert_eq!(*writer.writes.lock().unwrap(), [24]);
+    }
+
+    #[test]
+    fn partly_written_entries_are_errors() {
+        let writer = CountingWriter {
+            writes: Arc::default(),
+            limit: 10,
+        };
+        let target = FileTarget::with_writer(writer.clone(), 64);
+
+        let err = target
+            .write(b"\nlonger than ten bytes", &options(true))
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::WriteZero);
+        assert_eq!(*writer.writes.lock().unwrap(), [22]);
+    }
+}
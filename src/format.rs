@@ -0,0 +1,119 @@
+//! How log lines are laid out: `LogFormat`, and the line templates set with `Logger::set_format`.
+
+/// Whether log lines are text for people or JSON for machines, see `Logger.log_format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Lines in the format set with `Logger::set_format`, with extra fields appended as `key=value`.
+    #[default]
+    Plain,
+    /// One JSON object per line, with the fields `logger`, `level`, `timestamp` (RFC 3339) and `message`,
+    /// followed by the extra fields. Extra fields with one of those names become `fields.level` and so on. `Logger::set_format`, `Logger::set_time_format` and `Logger.wrap_width` don't apply.
+    Json,
+}
+
+/// A part of a line template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Piece {
+    Literal(String),
+    Name,
+    Level,
+    Timestamp,
+    Message,
+}
+
+/// Parses a line template like `"{timestamp} [{level}] {name}: {message}"`.
+///
+/// `{{` and `}}` stand for literal braces. Anything else in braces is an error,
+/// and so is a template without exactly one `{message}`.
+pub(crate) fn parse(template: &str) -> Result<Vec<Piece>, String> {
+    let mut pieces = Vec::new();
+    let mut literal = String::new();
+    let mut chars = template.char_indices().peekable();
+
+    while let Some((start, ch)) = chars.next() {
+        match ch {
+            '{' if chars.next_if(|&(_, ch)| ch == '{').is_some() => literal.push('{'),
+            '}' if chars.next_if(|&(_, ch)| ch == '}').is_some() => literal.push('}'),
+            '{' => {
+                let end = template[start..]
+                    .find('}')
+                    .map(|end| start + end)
+                    .ok_or_else(|| format!("unclosed `{{` in `{}`", template))?;
+
+                let piece = match &template[start + 1..end] {
+                    "name" => Piece::Name,
+                    "level" => Piece::Level,
+                    "timestamp" => Piece::Timestamp,
+                    "message" => Piece::Message,
+                    _ => {
+                        return Err(format!(
+                            "unknown placeholder `{}` in `{}`, expected `{{name}}`, `{{level}}`, `{{timestamp}}` or `{{message}}`",
+                            &template[start..=end],
+                            template
+                        ))
+                    }
+                };
+
+                while chars.next_if(|&(i, _)| i <= end).is_some() {}
+
+                if !literal.is_empty() {
+                    pieces.push(Piece::Literal(std::mem::take(&mut literal)));
+                }
+                pieces.push(piece);
+            }
+            '}' => return Err(format!("unmatched `}}` in `{}`", template)),
+            ch => literal.push(ch),
+        }
+    }
+
+    if !literal.is_empty() {
+        pieces.push(Piece::Literal(literal));
+    }
+
+    match pieces
+        .iter()
+        .filter(|piece| **piece == Piece::Message)
+        .count()
+    {
+        1 => Ok(pieces),
+        _ => Err(format!(
+            "`{}` must contain `{{message}}` exactly once",
+            template
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Piece};
+
+    #[test]
+    fn parses_placeholders_and_escaped_braces() {
+        assert_eq!(
+            parse("{timestamp} [{level}] {name}: {message} {{done}}").unwrap(),
+            [
+                Piece::Timestamp,
+                Piece::Literal(" [".to_string()),
+                Piece::Level,
+                Piece::Literal("] ".to_string()),
+                Piece::Name,
+                Piece::Literal(": ".to_string()),
+                Piece::Message,
+                Piece::Literal(" {done}".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        assert!(parse("{name}: {msg}").unwrap_err().contains("`{msg}`"));
+        assert!(parse("{name}: {message").unwrap_err().contains("unclosed"));
+        assert!(parse("{name}} {message}")
+            .unwrap_err()
+            .contains("unmatched"));
+        assert!(parse("{name}").unwrap_err().contains("exactly once"));
+        assert!(parse("{message} {message}")
+            .unwrap_err()
+            .contains("exactly once"));
+    }
+}
@@ -0,0 +1,122 @@
+//! The global `Logger` used by the logging macros, see `lawg::init`.
+
+use std::sync::OnceLock;
+
+use crate::{Logger, LoggerError};
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+/// Makes `logger` the global logger, used by `lawg::log!` and the other logging macros.
+///
+/// It can only be set once, and only before the macros first logged, after which they use the default
+/// logger from `lawg::logger`, so later calls return `LoggerError::AlreadyInitialized`.
+///
+/// The global logger is never dropped, so lines it keeps buffered, with a `FlushPolicy` other than
+/// `FlushPolicy::EveryLine` or with `Logger::set_async`, need a `lawg::logger().flush()` before the program exits.
+/// # Example
+/// ```rust,no_run
+/// use lawg::Logger;
+///
+/// lawg::init(Logger::new(String::from("My App"), Some(String::from("../logs/app.log")), true)).unwrap();
+///
+/// let id = 42;
+/// lawg::log!("User {} logged in", id); // My App - ["yyyy-mm-dd hh:mm:ss UTC"]: User 42 logged in
+/// lawg::log_file!("User {} logged in", id);
+/// ```
+pub fn init(logger: Logger) -> Result<(), LoggerError> {
+    LOGGER
+        .set(logger)
+        .map_err(|_| LoggerError::AlreadyInitialized)
+}
+
+/// The global logger set with `lawg::init`.
+///
+/// Without one, it's a console-only logger named after the program's executable,
+/// using local time (UTC without the `chrono` feature).
+pub fn logger() -> &'static Logger {
+    LOGGER.get_or_init(|| {
+        let name = std::env::current_exe()
+            .ok()
+            .and_then(|exe| Some(exe.file_stem()?.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| String::from("lawg"));
+
+        Logger::new(name, None, !cfg!(feature = "chrono"))
+    })
+}
+
+/// Logs to the console with the global logger, see `Logger::log`.
+/// Takes `format!`-style arguments.
+/// # Example
+/// ```rust
+/// let id = 42;
+/// lawg::log!("User {} logged in", id); // my-app - ["yyyy-mm-dd hh:mm:ss.f +hh:mm"]: User 42 logged in
+/// ```
+#[macro_export]
+macro_rules! log {
+    ($($arg:tt)+) => {
+        $crate::logger().log(::std::format_args!($($arg)+))
+    };
+}
+
+/// Logs to the file targets with the global logger, see `Logger::log_to_file`.
+/// Takes `format!`-style arguments.
+#[macro_export]
+macro_rules! log_file {
+    ($($arg:tt)+) => {
+        $crate::logger().log_to_file(::std::format_args!($($arg)+))
+    };
+}
+
+/// Logs an error to the console with the global logger, see `Logger::error`.
+/// Takes `format!`-style arguments.
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)+) => {
+        $crate::logger().error(::std::format_args!($($arg)+))
+    };
+}
+
+/// Logs an error to the file targets with the global logger, see `Logger::error_to_file`.
+/// Takes `format!`-style arguments.
+#[macro_export]
+macro_rules! error_file {
+    ($($arg:tt)+) => {
+        $crate::logger().error_to_file(::std::format_args!($($arg)+))
+    };
+}
+
+/// Logs at `Level::Trace` with the global logger, see `Logger::log_at`.
+/// Takes `format!`-style arguments.
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)+) => {
+        $crate::logger().trace(::std::format_args!($($arg)+))
+    };
+}
+
+/// Logs at `Level::Debug` with the global logger, see `Logger::log_at`.
+/// Takes `format!`-style arguments.
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)+) => {
+        $crate::logger().debug(::std::format_args!($($arg)+))
+    };
+}
+
+/// Logs at `Level::Info` with the global logger, see `Logger::log_at`.
+/// Takes `format!`-style arguments.
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)+) => {
+        $crate::logger().info(::std::format_args!($($arg)+))
+    };
+}
+
+/// Logs at `Level::Warn` with the global logger, see `Logger::log_at`.
+/// Takes `format!`-style arguments.
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)+) => {
+        $crate::logger().warn(::std::format_args!($($arg)+))
+    };
+}
@@ -0,0 +1,47 @@
+//! JSON log lines, see `LogFormat::Json`.
+
+use std::fmt::{self, Write};
+
+/// Writes `value` to `line` as a JSON string, quotes included.
+pub(crate) fn write_string<T: fmt::Display>(line: &mut String, value: T) {
+    line.push('"');
+    write!(Escaped(line), "{}", value).unwrap();
+    line.push('"');
+}
+
+/// Escapes everything written through it for use inside a JSON string.
+///
+/// Quotes, backslashes and control characters are escaped, everything else,
+/// non-ASCII included, is valid in JSON strings as it is.
+struct Escaped<'a>(&'a mut String);
+
+impl Write for Escaped<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for ch in s.chars() {
+            match ch {
+                '"' => self.0.push_str("\\\""),
+                '\\' => self.0.push_str("\\\\"),
+                '\n' => self.0.push_str("\\n"),
+                '\r' => self.0.push_str("\\r"),
+                '\t' => self.0.push_str("\\t"),
+                ch if ch < ' ' => write!(self.0, "\\u{:04x}", ch as u32)?,
+                ch => self.0.push(ch),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::write_string;
+
+    #[test]
+    fn escapes_special_characters() {
+        let mut line = String::new();
+        write_string(&mut line, "say \"hi\"\\\n\tnow\u{1}, ünïcödé 日本");
+
+        assert_eq!(line, "\"say \\\"hi\\\"\\\\\\n\\tnow\\u0001, ünïcödé 日本\"");
+    }
+}
@@ -0,0 +1,53 @@
+//! Log levels.
+
+use std::fmt;
+
+/// How important a log message is, from least (`Trace`) to most (`Error`) important.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    /// The level's name, like `"WARN"`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Level::Trace => "TRACE",
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warn => "WARN",
+            Level::Error => "ERROR",
+        }
+    }
+
+    /// What log lines of this level start with, like `"WARN: "`.
+    pub(crate) fn prefix(self) -> &'static str {
+        match self {
+            Level::Trace => "TRACE: ",
+            Level::Debug => "DEBUG: ",
+            Level::Info => "INFO: ",
+            Level::Warn => "WARN: ",
+            Level::Error => "ERROR: ",
+        }
+    }
+
+    pub(crate) fn from_u8(level: u8) -> Self {
+        match level {
+            0 => Level::Trace,
+            1 => Level::Debug,
+            2 => Level::Info,
+            3 => Level::Warn,
+            _ => Level::Error,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
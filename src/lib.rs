@@ -1,6 +1,6 @@
 //! lawg is a Rust library designed to log and create log files with ease.
 //! # Example
-//! ```rust
+//! ```rust,no_run
 //! use lawg::Logger;
 //!
 //! fn main() {
@@ -9,7 +9,7 @@
 //!     logger.log("Started"); // My Logger - ["yyyy-mm-dd hh:mm:ss UTC"]: Started
 //!     logger.log_to_file("Started again");
 //!
-//!     let mut x = 1 + 1;
+//!     let x = 1 + 1;
 //!
 //!     if x == 2 {
 //!         logger.log_and_log_to_file(String::from("It is two")); // My Logger - ["yyyy-mm-dd hh:mm:ss UTC"]: It is two
@@ -19,263 +19,2617 @@
 //! }
 //! ```
 
-use std::fs;
+use std::io::Write;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
+#[cfg(feature = "chrono")]
+use chrono::DateTime;
+#[cfg(feature = "chrono")]
 use chrono::Local;
+#[cfg(feature = "chrono")]
 use chrono::Utc;
 
+mod background;
+mod color;
+mod console;
+#[cfg(feature = "chrono")]
+mod entry;
+mod error;
+mod file;
+mod format;
+mod global;
+mod json;
+mod level;
+mod rotation;
+mod scratch;
+mod suppress;
+mod time;
+mod wrap;
+
+pub use background::OverflowPolicy;
+pub use color::ColorMode;
+pub use console::{ConsoleCoordinator, ConsoleTarget};
+#[cfg(feature = "chrono")]
+pub use entry::LogEntry;
+pub use error::LoggerError;
+pub use file::FlushPolicy;
+pub use format::LogFormat;
+pub use global::{init, logger};
+pub use level::Level;
+pub use rotation::RotationPolicy;
+
 /// The `Logger` struct, used for logging.
+///
+/// A `Logger` is `Send + Sync`, so threads can share one, e.g. in an `Arc`.
+/// Each line reaches the console and the file whole, without interleaving with other threads' lines.
 #[derive(Debug)]
 pub struct Logger {
     pub logger_name: String,
+    /// The path of the log file of the `"default"` file target, see `Logger::add_file_target`.
+    /// The file is opened once, by the constructor, so changing this later has no effect.
     pub file_log: Option<String>,
     pub use_utc: bool,
+    /// Whether `error_and_stop_to_file` also logs the error to the console before stopping.
+    /// Defaults to `true`.
+    pub echo_fatal_to_console: bool,
+    /// When set, console messages are word-wrapped to this many columns, with continuation
+    /// lines indented to where the message starts. File output is never wrapped.
+    /// Defaults to `None`.
+    pub wrap_width: Option<usize>,
+    /// Whether each entry must reach the log file in a single `write` call, see `Logger::new_append_only`.
+    /// Defaults to `false`.
+    pub append_only: bool,
+    /// When set, each log file is rotated according to it, see `RotationPolicy`.
+    /// Rotation assumes this `Logger` is the only one writing to the file.
+    /// With `Logger.append_only`, writing to the files is refused with `LoggerError::AppendOnly` instead.
+    /// Defaults to `None`, so the file grows forever.
+    pub rotation: Option<RotationPolicy>,
+    /// How many rotated files are kept, deleting the oldest ones. Defaults to `None`, keeping all of them.
+    pub max_backups: Option<usize>,
+    /// When lines are flushed to the log files, see `FlushPolicy`.
+    /// Defaults to `FlushPolicy::EveryLine`.
+    pub flush_policy: FlushPolicy,
+    /// Whether lines are written as text or as JSON, to the console and to the file.
+    /// Defaults to `LogFormat::Plain`.
+    pub log_format: LogFormat,
+    /// Which stream console lines go to. Defaults to `ConsoleTarget::Auto`, sending errors to stderr.
+    pub console_target: ConsoleTarget,
+    /// Whether console lines are colored: errors red, warnings yellow and the logger's name bold.
+    /// JSON lines and the log file are never colored. Defaults to `ColorMode::Never`.
+    pub color_mode: ColorMode,
+    min_level: AtomicU8,
+    line_format: Option<Vec<format::Piece>>,
+    time_format: Option<String>,
+    console_coordinator: Option<console::Coordinator>,
+    stdout: console::Stream,
+    stderr: console::Stream,
+    /// Shared with the `Logger`'s children.
+    targets: Vec<Arc<file::FileTarget>>,
+    buffer_size: usize,
+    suppressor: suppress::Suppressor,
+    /// Set by `Logger::set_async`, and shared with the `Logger`'s children.
+    background: Option<Arc<background::Writer>>,
+}
+
+/// The name of the file target made from `Logger.file_log`.
+const DEFAULT_TARGET: &str = "default";
+
+/// The size of each log file's buffer, unless `Logger::set_buffer_size` changes it.
+const DEFAULT_BUFFER_SIZE: usize = 8 * 1024;
+
+/// Flushes the log files, so nothing buffered is lost.
+impl Drop for Logger {
+    fn drop(&mut self) {
+        self.try_flush().ok();
+    }
 }
 
 impl Logger {
     /// Creates a new `Logger` struct.
-    /// If `file_log` is provided, it will check if the file exists.
-    /// If it does, it will do a read and write test on it, otherwise it will create a new file `file_log`.
+    /// If `file_log` is provided, it's opened for appending and kept open,
+    /// creating the file and any missing parent directories if they don't exist.
+    ///
+    /// Local time needs the `chrono` feature, so without it `use_utc` must be `true`.
     /// # Example
-    /// ```rust
+    /// ```rust,no_run
     /// use lawg::Logger;
     ///
-    /// let my_logger = Logger::new("My Logger", Some("../logs/log_file.txt"), true);
-    /// let another_logger = Logger::new("My Another Logger", None, false);
+    /// let my_logger = Logger::new(String::from("My Logger"), Some(String::from("../logs/log_file.txt")), true);
+    /// let another_logger = Logger::new(String::from("My Another Logger"), None, false);
     /// ```
     pub fn new(logger_name: String, file_log: Option<String>, use_utc: bool) -> Self {
-        if let Some(file) = file_log.clone() {
-            let mut file_log_content = String::new();
+        let path = file_log.clone();
 
-            if std::path::Path::new(&file.clone()).exists() {
-                file_log_content = fs::read_to_string(file.clone())
-                .unwrap_or_else(|_| panic!("Could not read log file `{}`", file));
-            }
+        Self::try_new(logger_name, file_log, use_utc)
+            .unwrap_or_else(|err| fail(err, "Could not create log file", path.as_deref()))
+    }
 
-            fs::write(file.clone(), file_log_content.as_bytes())
-                .unwrap_or_else(|_| panic!("Could not create log file `{}`", file));
+    /// Like `Logger::new`, but returns an error instead of panicking.
+    pub fn try_new(
+        logger_name: String,
+        file_log: Option<String>,
+        use_utc: bool,
+    ) -> Result<Self, LoggerError> {
+        #[cfg(not(feature = "chrono"))]
+        if !use_utc {
+            return Err(LoggerError::LocalTimeUnavailable);
         }
 
-        Logger {
+        let targets = file_log
+            .as_deref()
+            .map(|path| {
+                file::FileTarget::open(
+                    DEFAULT_TARGET,
+                    path,
+                    Level::Trace,
+                    DEFAULT_BUFFER_SIZE,
+                    use_utc,
+                )
+                .map(Arc::new)
+            })
+            .into_iter()
+            .collect::<Result<_, _>>()?;
+
+        Ok(Logger {
+            targets,
             logger_name,
             file_log,
             use_utc,
+            echo_fatal_to_console: true,
+            wrap_width: None,
+            append_only: false,
+            rotation: None,
+            max_backups: None,
+            flush_policy: FlushPolicy::EveryLine,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            log_format: LogFormat::Plain,
+            console_target: ConsoleTarget::Auto,
+            color_mode: ColorMode::Never,
+            min_level: AtomicU8::new(Level::Trace as u8),
+            line_format: None,
+            time_format: None,
+            console_coordinator: None,
+            stdout: console::Stream::Stdout,
+            stderr: console::Stream::Stderr,
+            suppressor: suppress::Suppressor::default(),
+            background: None,
+        })
+    }
+
+    /// Creates a new `Logger` struct whose log file `file_log` only ever grows.
+    ///
+    /// Like with `Logger::new`, the file and its parent directories are created if they don't exist.
+    /// The file is only opened for appending (`O_APPEND`, or `FILE_APPEND_DATA` on Windows).
+    /// Every entry is written with exactly one `write` call, so entries appended by several processes
+    /// can't interleave within a line. An entry the OS only writes partly is a panic.
+    /// The file is never rotated or pruned: with `Logger.rotation` set, writing to it fails with
+    /// `LoggerError::AppendOnly` before anything is renamed or removed.
+    ///
+    /// The atomicity comes from the OS and has limits: POSIX only promises it for writes up to
+    /// `PIPE_BUF` (often 4 KiB) on pipes, local filesystems usually but not always extend that to
+    /// larger writes, and NFS does not make `O_APPEND` writes atomic at all.
+    /// # Example
+    /// ```rust,no_run
+    /// use lawg::Logger;
+    ///
+    /// let audit_logger = Logger::new_append_only(String::from("Audit"), String::from("../logs/audit.txt"), true);
+    /// audit_logger.log_to_file("User 42 deleted order 7");
+    /// ```
+    pub fn new_append_only(logger_name: String, file_log: String, use_utc: bool) -> Self {
+        let path = file_log.clone();
+
+        Self::try_new_append_only(logger_name, file_log, use_utc)
+            .unwrap_or_else(|err| fail(err, "Could not create log file", Some(&path)))
+    }
+
+    /// Like `Logger::new_append_only`, but returns an error instead of panicking.
+    pub fn try_new_append_only(
+        logger_name: String,
+        file_log: String,
+        use_utc: bool,
+    ) -> Result<Self, LoggerError> {
+        let mut logger = Logger::try_new(logger_name, None, use_utc)?;
+
+        logger.targets.push(Arc::new(file::FileTarget::open(
+            DEFAULT_TARGET,
+            &file_log,
+            Level::Trace,
+            logger.buffer_size,
+            use_utc,
+        )?));
+        logger.file_log = Some(file_log);
+        logger.append_only = true;
+
+        Ok(logger)
+    }
+
+    /// Creates a logger named `parent::name`, like `app::db`, that writes to the same file targets
+    /// and console streams as this one. Children can have children of their own.
+    ///
+    /// The child starts with a copy of this logger's settings, and changing them afterwards on either one
+    /// doesn't affect the other. It also starts without the keys seen by `Logger::log_once` and
+    /// `Logger::log_every`. Only the open files are shared: file targets added to or removed from one logger
+    /// don't change the other's, but `Logger::set_buffer_size` resizes the shared files' buffers for both.
+    /// If this logger is async, the child queues its lines for the same background thread.
+    /// # Example
+    /// ```rust,no_run
+    /// use lawg::Logger;
+    ///
+    /// let app = Logger::new(String::from("app"), Some(String::from("../logs/app.log")), true);
+    /// let db = app.child("db");
+    ///
+    /// db.log_and_log_to_file(String::from("Connection pool ready")); // app::db - ["yyyy-mm-dd hh:mm:ss UTC"]: Connection pool ready
+    /// ```
+    pub fn child(&self, name: &str) -> Logger {
+        Logger {
+            logger_name: format!("{}::{}", self.logger_name, name),
+            file_log: self.file_log.clone(),
+            use_utc: self.use_utc,
+            echo_fatal_to_console: self.echo_fatal_to_console,
+            wrap_width: self.wrap_width,
+            append_only: self.append_only,
+            rotation: self.rotation,
+            max_backups: self.max_backups,
+            flush_policy: self.flush_policy,
+            log_format: self.log_format,
+            console_target: self.console_target,
+            color_mode: self.color_mode,
+            min_level: AtomicU8::new(self.min_level.load(Ordering::Relaxed)),
+            line_format: self.line_format.clone(),
+            time_format: self.time_format.clone(),
+            console_coordinator: self.console_coordinator.clone(),
+            stdout: self.stdout.clone(),
+            stderr: self.stderr.clone(),
+            targets: self.targets.clone(),
+            buffer_size: self.buffer_size,
+            suppressor: suppress::Suppressor::default(),
+            background: self.background.clone(),
         }
     }
 
-    /// Logs to the console.
+    /// Adds a log file `path` named `name`, which gets the lines written by the `_to_file` methods
+    /// whose level is at least `min_level`. An existing target named `name` is replaced.
+    ///
+    /// The target made from `Logger.file_log` is named `"default"` and gets every level.
+    /// `Logger::set_min_level` still applies to all of them.
     /// # Example
-    /// ```rust
+    /// ```rust,no_run
+    /// use lawg::{Level, Logger};
+    ///
+    /// let mut my_logger = Logger::new(String::from("My Logger"), Some(String::from("../logs/app.log")), true);
+    /// my_logger.add_file_target("errors", "../logs/errors.log", Level::Error);
+    ///
+    /// my_logger.log_to_file("Only in app.log");
+    /// my_logger.error_to_file("In app.log and errors.log");
+    /// ```
+    pub fn add_file_target(&mut self, name: &str, path: &str, min_level: Level) {
+        self.try_add_file_target(name, path, min_level)
+            .unwrap_or_else(|err| fail(err, "Could not create log file", Some(path)));
+    }
+
+    /// Like `Logger::add_file_target`, but returns an error instead of panicking.
+    pub fn try_add_file_target(
+        &mut self,
+        name: &str,
+        path: &str,
+        min_level: Level,
+    ) -> Result<(), LoggerError> {
+        let target = file::FileTarget::open(name, path, min_level, self.buffer_size, self.use_utc)?;
+
+        self.remove_file_target(name);
+        self.targets.push(Arc::new(target));
+
+        Ok(())
+    }
+
+    /// Removes the file target named `name`, closing its file. Returns whether there was one.
+    pub fn remove_file_target(&mut self, name: &str) -> bool {
+        let len = self.targets.len();
+        self.targets.retain(|target| target.name != name);
+
+        self.targets.len() < len
+    }
+
+    /// Sets the size in bytes of each log file's buffer, flushing what's in them. Defaults to 8 KiB.
+    /// How often the buffers are flushed before they're full is up to `Logger.flush_policy`.
+    pub fn set_buffer_size(&mut self, buffer_size: usize) {
+        self.buffer_size = buffer_size;
+        self.resize_buffers()
+            .unwrap_or_else(|err| self.file_failed(err));
+    }
+
+    /// Like `Logger::set_buffer_size`, but returns an error instead of panicking.
+    pub fn try_set_buffer_size(&mut self, buffer_size: usize) -> Result<(), LoggerError> {
+        self.buffer_size = buffer_size;
+        Ok(self.resize_buffers()?)
+    }
+
+    /// Gives every log file a buffer of `Logger.buffer_size` bytes.
+    fn resize_buffers(&self) -> Result<(), file::FileError<'_>> {
+        for target in &self.targets {
+            target
+                .set_buffer_size(self.buffer_size, self.use_utc)
+                .map_err(|err| file::FileError {
+                    err: err.into(),
+                    path: Some(&target.path),
+                })?;
+        }
+
+        Ok(())
+    }
+
+    /// Flushes the lines buffered for the log files, and the console.
+    /// # Example
+    /// ```rust,no_run
+    /// use lawg::{FlushPolicy, Logger};
+    ///
+    /// let mut my_logger = Logger::new(String::from("My Logger"), Some(String::from("../logs/log_file.txt")), true);
+    /// my_logger.flush_policy = FlushPolicy::Manual;
+    ///
+    /// for i in 0..50_000 {
+    ///     my_logger.log_to_file(i);
+    /// }
+    /// my_logger.flush();
+    /// ```
+    pub fn flush(&self) {
+        self.flush_files()
+            .unwrap_or_else(|err| self.file_failed(err));
+        self.stdout
+            .flush()
+            .and_then(|()| self.stderr.flush())
+            .unwrap_or_else(|err| fail(err.into(), "Could not print to the console", None));
+    }
+
+    /// Like `Logger::flush`, but returns an error instead of panicking.
+    pub fn try_flush(&self) -> Result<(), LoggerError> {
+        self.flush_files()?;
+        self.stdout.flush()?;
+        self.stderr.flush()?;
+
+        Ok(())
+    }
+
+    /// Makes the `_to_file` methods queue their lines for a background thread to write,
+    /// so the calling thread doesn't wait for the files. The queue holds up to `capacity` lines,
+    /// and `policy` says what a log call does when it's full. Console lines are still printed right away.
+    ///
+    /// An error from writing a queued line can't reach the call that logged it,
+    /// so the next `Logger::flush` returns it instead. `Logger::flush` waits for the queue to be written,
+    /// and so do `Logger::shutdown` and dropping the logger.
+    /// If the background thread dies, the `_to_file` methods and `Logger::flush` fail instead of waiting for it.
+    /// # Example
+    /// ```rust,no_run
+    /// use lawg::{Logger, OverflowPolicy};
+    ///
+    /// let mut my_logger = Logger::new(String::from("My Logger"), Some(String::from("../logs/log_file.txt")), true);
+    /// my_logger.set_async(10_000, OverflowPolicy::DropOldest);
+    ///
+    /// my_logger.log_to_file("Written by the background thread");
+    /// println!("{} lines dropped", my_logger.dropped_count());
+    /// ```
+    pub fn set_async(&mut self, capacity: usize, policy: OverflowPolicy) {
+        self.try_set_async(capacity, policy)
+            .unwrap_or_else(|err| fail(err, "Could not start the background writer", None));
+    }
+
+    /// Like `Logger::set_async`, but returns an error instead of panicking.
+    pub fn try_set_async(
+        &mut self,
+        capacity: usize,
+        policy: OverflowPolicy,
+    ) -> Result<(), LoggerError> {
+        self.background = Some(Arc::new(background::Writer::spawn(capacity, policy)?));
+        Ok(())
+    }
+
+    /// Writes every queued line and flushes the files, then goes back to writing them on the calling thread.
+    /// The background thread stops, unless children of the logger still use it.
+    pub fn shutdown(&mut self) {
+        self.flush_files()
+            .unwrap_or_else(|err| self.file_failed(err));
+        self.background = None;
+    }
+
+    /// Like `Logger::shutdown`, but returns an error instead of panicking.
+    pub fn try_shutdown(&mut self) -> Result<(), LoggerError> {
+        self.flush_files()?;
+        self.background = None;
+
+        Ok(())
+    }
+
+    /// How many lines `OverflowPolicy::DropOldest` dropped because the queue was full,
+    /// counting the children sharing the queue, since `Logger::set_async`. `0` if the logger isn't async.
+    pub fn dropped_count(&self) -> u64 {
+        self.background
+            .as_ref()
+            .map_or(0, |background| background.dropped())
+    }
+
+    /// Flushes the lines buffered for the log files, after the queued ones are written.
+    fn flush_files(&self) -> Result<(), file::FileError<'_>> {
+        if let Some(background) = &self.background {
+            background
+                .wait_until_written()
+                .map_err(|err| file::FileError {
+                    err: err.into(),
+                    path: None,
+                })?;
+        }
+
+        for target in &self.targets {
+            target.flush().map_err(|err| file::FileError {
+                err: err.into(),
+                path: Some(&target.path),
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the entries of the log file `Logger.file_log` back, oldest first,
+    /// including the ones other loggers wrote to it. Lines still buffered are flushed first.
+    ///
+    /// Only lines in the default format, without `Logger::set_format`, `Logger::set_time_format`
+    /// or `LogFormat::Json`, can be read back. Lines that can't be read are skipped,
+    /// with a warning on the console.
+    /// # Example
+    /// ```rust,no_run
     /// use lawg::Logger;
     ///
-    /// let my_logger = Logger::new("My Logger", Some("../logs/log_file.txt"), true);
-    /// my_logger.log("This is a log"); // My Logger - ["yyyy-mm-dd hh:mm:ss UTC"]: This is a log
+    /// let my_logger = Logger::new(String::from("My Logger"), Some(String::from("../logs/log_file.txt")), true);
+    /// my_logger.error_to_file("Cereals are gone");
+    ///
+    /// for entry in my_logger.read_entries().unwrap() {
+    ///     println!("{} {} {}: {}", entry.timestamp, entry.level, entry.logger_name, entry.message); // yyyy-mm-dd hh:mm:ss UTC ERROR My Logger: Cereals are gone
+    /// }
     /// ```
-    pub fn log<T: std::fmt::Display>(&self, msg: T) {
-        let to_log = format!(
-            "{} - [{:?}]: {}",
-            self.logger_name,
-            {
-                if self.use_utc {
-                    Utc::now().to_string()
-                } else {
-                    Local::now().to_string()
-                }
-            },
-            msg
-        );
+    #[cfg(feature = "chrono")]
+    pub fn read_entries(&self) -> Result<Vec<LogEntry>, LoggerError> {
+        if self.log_format == LogFormat::Json
+            || self.line_format.is_some()
+            || self.time_format.is_some()
+        {
+            return Err(LoggerError::InvalidFormat(
+                "only lines in the default format can be read back".to_string(),
+            ));
+        }
+
+        let path = self
+            .targets
+            .iter()
+            .find(|target| target.name == DEFAULT_TARGET)
+            .map(|target| target.path.as_str())
+            .ok_or(LoggerError::NoLogFile)?;
+
+        self.flush_files()?;
+        let content = std::fs::read_to_string(path)?;
+
+        Ok(entry::parse(&content, |line, reason| {
+            let warning = format_args!("Skipping line {} of {}: {}", line, path, reason);
+            self.line_to_console(Level::Warn, Level::Warn.prefix(), warning, &[])
+                .ok();
+        }))
+    }
+
+    /// The last `n` entries of the log file, oldest first, see `Logger::read_entries`.
+    #[cfg(feature = "chrono")]
+    pub fn tail(&self, n: usize) -> Result<Vec<LogEntry>, LoggerError> {
+        let mut entries = self.read_entries()?;
+        entries.drain(..entries.len().saturating_sub(n));
+
+        Ok(entries)
+    }
+
+    /// The entries of the log file written at `since` or later, oldest first, see `Logger::read_entries`.
+    #[cfg(feature = "chrono")]
+    pub fn entries_since(&self, since: DateTime<Utc>) -> Result<Vec<LogEntry>, LoggerError> {
+        let mut entries = self.read_entries()?;
+        entries.retain(|entry| entry.timestamp >= since);
+
+        Ok(entries)
+    }
+
+    /// Sets the least important level that is logged, to the console and to the file.
+    /// Messages below it are dropped. Defaults to `Level::Trace`, so everything is logged.
+    ///
+    /// `log` and `log_to_file` log at `Level::Info`, and the `error` methods at `Level::Error`.
+    /// # Example
+    /// ```rust
+    /// use lawg::{Level, Logger};
+    ///
+    /// let my_logger = Logger::new(String::from("My Logger"), None, true);
+    /// my_logger.set_min_level(Level::Warn);
+    /// my_logger.info("Not shown");
+    /// my_logger.warn("Shown"); // WARN: My Logger - ["yyyy-mm-dd hh:mm:ss UTC"]: Shown
+    /// ```
+    pub fn set_min_level(&self, level: Level) {
+        self.min_level.store(level as u8, Ordering::Relaxed);
+    }
 
-        println!("{}", to_log);
+    /// Returns the least important level that is logged, see `Logger::set_min_level`.
+    pub fn min_level(&self) -> Level {
+        Level::from_u8(self.min_level.load(Ordering::Relaxed))
     }
 
-    /// Logs to file `Logger.file_log` (and not shown on the console).
+    /// Sets the template of log lines, on the console and in the file, with the placeholders
+    /// `{name}`, `{level}`, `{timestamp}` and `{message}`. `{{` and `}}` are literal braces.
+    ///
+    /// `{level}` is the level's name, like `INFO`, also for `log` and `log_to_file`, whose lines don't show it by default.
+    /// The template must contain `{message}` exactly once, and unknown placeholders are an error.
+    /// Until this is called, lines look like `ERROR: My Logger - ["yyyy-mm-dd hh:mm:ss UTC"]: msg`.
     /// # Example
     /// ```rust
     /// use lawg::Logger;
     ///
-    /// let my_logger = Logger::new("My Logger", Some("../logs/log_file.txt"), true);
-    /// my_logger.log_to_file("This is log is written on the file and not shown on the console.");
+    /// let mut my_logger = Logger::new(String::from("My Logger"), None, true);
+    /// my_logger.set_format("{timestamp} [{level}] {name}: {message}").unwrap();
+    /// my_logger.warn("Running low on cereals"); // yyyy-mm-dd hh:mm:ss UTC [WARN] My Logger: Running low on cereals
     /// ```
-    pub fn log_to_file<T: std::fmt::Display>(&self, msg: T) {
-        if let Some(file_log) = &self.file_log {
-            let file_log_content = fs::read_to_string(file_log.clone())
-                .unwrap_or_else(|_| panic!("Could not read log file `{}`", file_log.clone()));
-
-            fs::write(
-                &file_log.clone(),
-                (file_log_content
-                    + "\n"
-                    + &format!(
-                        "{} - [{:?}]: {}",
-                        self.logger_name,
-                        {
-                            if self.use_utc {
-                                Utc::now().to_string()
-                            } else {
-                                Local::now().to_string()
-                            }
-                        },
-                        msg
-                    ))
-                    .as_bytes(),
-            )
-            .unwrap_or_else(|_| panic!("Could not create log file `{}`", file_log.clone()));
-        } else {
-            panic!("Log file not provided.");
-        }
+    pub fn set_format(&mut self, template: &str) -> Result<(), LoggerError> {
+        self.line_format = Some(format::parse(template).map_err(LoggerError::InvalidFormat)?);
+        Ok(())
     }
 
-    /// Logs to the console and file `Logger.file_log`.
+    /// Sets the strftime-style format of `{timestamp}`, like `%Y-%m-%d %H:%M:%S`.
+    /// Unsupported specifiers are an error.
+    ///
+    /// With the `chrono` feature every chrono specifier is supported. Without it the common ones are:
+    /// `%Y %y %m %b %h %B %d %e %a %A %H %I %p %M %S %f %.f %.3f %.6f %.9f %3f %6f %9f %s %z %:z %Z %+ %F %T %R %D %% %n %t`.
     /// # Example
     /// ```rust
     /// use lawg::Logger;
     ///
-    /// let my_logger = Logger::new("My Logger", Some("../logs/log_file.txt"), true);
-    /// my_logger.log_and_log_to_file("This log will appear on the console and also be written to the file"); // My Logger - ["yyyy-mm-dd hh:mm:ss UTC"]: This log will appear on the console and also be written to the file
+    /// let mut my_logger = Logger::new(String::from("My Logger"), None, true);
+    /// my_logger.set_time_format("%H:%M:%S%.3f").unwrap();
+    /// my_logger.log("Hello world"); // My Logger - ["hh:mm:ss.sss"]: Hello world
     /// ```
-    pub fn log_and_log_to_file(&self, msg: String) {
-        self.log(msg.clone());
-        self.log_to_file(msg);
+    pub fn set_time_format(&mut self, format: &str) -> Result<(), LoggerError> {
+        time::check_format(format).map_err(LoggerError::InvalidFormat)?;
+        self.time_format = Some(format.to_string());
+        Ok(())
+    }
+
+    /// Returns the current time, for a log line.
+    fn timestamp(&self) -> time::Timestamp {
+        #[cfg(feature = "chrono")]
+        {
+            if self.use_utc {
+                time::Timestamp::Utc(Utc::now())
+            } else {
+                time::Timestamp::Local(Local::now())
+            }
+        }
+
+        #[cfg(not(feature = "chrono"))]
+        {
+            time::Timestamp::Std(time::UtcTime::now())
+        }
+    }
+
+    /// Returns the width of the terminal attached to stdout, if there is one.
+    /// Useful as `Logger.wrap_width`.
+    #[cfg(feature = "terminal_size")]
+    pub fn terminal_width() -> Option<usize> {
+        terminal_size::terminal_size().map(|(width, _)| width.0 as usize)
     }
 
-    /// Logs an error to the console.
+    /// Sets a `ConsoleCoordinator` to run around every line written to the console.
+    pub fn set_console_coordinator<C: ConsoleCoordinator + 'static>(&mut self, coordinator: C) {
+        self.console_coordinator = Some(console::Coordinator(Arc::new(coordinator)));
+    }
+
+    /// Replaces stdout and stderr as the streams console lines are written to, e.g. to capture them in tests.
+    /// `Logger.console_target` still picks which of the two a line goes to.
     /// # Example
     /// ```rust
     /// use lawg::Logger;
+    /// use std::io::Write;
+    /// use std::sync::{Arc, Mutex};
     ///
-    /// let my_logger = Logger::new("My Logger", Some("../logs/log_file.txt"), true);
-    /// my_logger.error("Something went wrong! Try again later"); // ERROR: My Logger - ["yyyy-mm-dd hh:mm:ss UTC"]: Something went wrong! Try again later
+    /// #[derive(Clone, Default)]
+    /// struct Captured(Arc<Mutex<Vec<u8>>>);
+    ///
+    /// impl Write for Captured {
+    ///     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    ///         self.0.lock().unwrap().write(buf)
+    ///     }
+    ///
+    ///     fn flush(&mut self) -> std::io::Result<()> {
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let (out, err) = (Captured::default(), Captured::default());
+    /// let mut my_logger = Logger::new(String::from("My Logger"), None, true);
+    /// my_logger.set_console_writers(out.clone(), err.clone());
+    /// my_logger.error("Cereals are gone");
+    ///
+    /// assert!(out.0.lock().unwrap().is_empty());
+    /// assert!(!err.0.lock().unwrap().is_empty());
     /// ```
-    pub fn error<T: std::fmt::Display>(&self, msg: T) {
-        let to_log = format!(
-            "ERROR: {} - [{:?}]: {}",
-            self.logger_name,
-            {
-                if self.use_utc {
-                    Utc::now().to_string()
+    pub fn set_console_writers<O, E>(&mut self, stdout: O, stderr: E)
+    where
+        O: Write + Send + 'static,
+        E: Write + Send + 'static,
+    {
+        self.stdout = console::Stream::Writer(Arc::new(Mutex::new(Box::new(stdout))));
+        self.stderr = console::Stream::Writer(Arc::new(Mutex::new(Box::new(stderr))));
+    }
+
+    /// Writes a log line to `line`, as JSON if `Logger.log_format` says so, otherwise in the format
+    /// set with `Logger::set_format`, or by default like `ERROR: My Logger - ["yyyy-mm-dd hh:mm:ss UTC"]: msg`,
+    /// starting with `prefix`. Plain lines end with the `fields` as ` key=value`,
+    /// and their level and name are `colored` if asked for.
+    /// Returns where the message starts.
+    fn write_line<T: std::fmt::Display>(
+        &self,
+        line: &mut String,
+        level: Level,
+        prefix: &str,
+        msg: T,
+        fields: &[(&str, &str)],
+        colored: bool,
+    ) -> usize {
+        use std::fmt::Write;
+
+        let timestamp = self.timestamp();
+
+        if self.log_format == LogFormat::Json {
+            line.push_str("{\"logger\":");
+            json::write_string(line, &self.logger_name);
+            write!(
+                line,
+                ",\"level\":\"{}\",\"timestamp\":\"{}\",\"message\":",
+                level,
+                timestamp.rfc3339()
+            )
+            .unwrap();
+            let msg_start = line.len();
+            json::write_string(line, msg);
+
+            for (key, value) in fields {
+                line.push(',');
+
+                // Fields named like the built-in ones would make duplicate keys.
+                match *key {
+                    "logger" | "level" | "timestamp" | "message" => {
+                        json::write_string(line, format_args!("fields.{}", key))
+                    }
+                    key => json::write_string(line, key),
+                }
+                line.push(':');
+                json::write_string(line, value);
+            }
+            line.push('}');
+
+            return msg_start;
+        }
+
+        let timestamp = timestamp.with_format(self.time_format.as_deref());
+        let write_message = |line: &mut String| {
+            let msg_start = line.len();
+            write!(line, "{}", msg).unwrap();
+
+            for (key, value) in fields {
+                write!(line, " {}=", key).unwrap();
+
+                // Values that would be ambiguous unquoted are quoted, like `msg="two words"`.
+                if value.is_empty() || value.contains([' ', '"', '=', '\n']) {
+                    write!(line, "{:?}", value).unwrap();
                 } else {
-                    Local::now().to_string()
+                    line.push_str(value);
                 }
-            },
-            msg
-        );
+            }
+
+            msg_start
+        };
+
+        let (level_color, name_color) = match colored {
+            true => (color::of_level(level), Some(color::BOLD)),
+            false => (None, None),
+        };
+
+        let Some(pieces) = &self.line_format else {
+            if !prefix.is_empty() {
+                color::paint(line, level_color, prefix.trim_end());
+                line.push(' ');
+            }
+            color::paint(line, name_color, &self.logger_name);
+            write!(line, " - [\"{}\"]: ", timestamp).unwrap();
+
+            return write_message(line);
+        };
+
+        let mut msg_start = line.len();
+        let mut write_message = Some(write_message);
+
+        for piece in pieces {
+            match piece {
+                format::Piece::Literal(literal) => line.push_str(literal),
+                format::Piece::Name => color::paint(line, name_color, &self.logger_name),
+                format::Piece::Level => color::paint(line, level_color, level),
+                format::Piece::Timestamp => write!(line, "{}", timestamp).unwrap(),
+                format::Piece::Message => {
+                    // Templates contain `{message}` exactly once.
+                    if let Some(write_message) = write_message.take() {
+                        msg_start = write_message(line);
+                    }
+                }
+            }
+        }
+
+        msg_start
+    }
+
+    /// Prints a log line of `level` to the console stream `Logger.console_target` picks,
+    /// unless it's below `Logger::min_level`.
+    /// It's wrapped if `Logger.wrap_width` is set
+    /// and through the `ConsoleCoordinator` if there is one.
+    ///
+    /// The whole line, newline included, is written with one `write_all` while holding the stream's lock,
+    /// so lines from different threads don't interleave.
+    fn line_to_console<T: std::fmt::Display>(
+        &self,
+        level: Level,
+        prefix: &str,
+        msg: T,
+        fields: &[(&str, &str)],
+    ) -> Result<(), LoggerError> {
+        if level < self.min_level() {
+            return Ok(());
+        }
+
+        scratch::with_scratch(|line| {
+            let stream = match self.console_target.is_stderr(level) {
+                true => &self.stderr,
+                false => &self.stdout,
+            };
+            let colored = self.log_format == LogFormat::Plain
+                && match self.color_mode {
+                    ColorMode::Always => true,
+                    ColorMode::Never => false,
+                    ColorMode::Auto => stream.auto_colors(),
+                };
+
+            let msg_start = self.write_line(line, level, prefix, msg, fields, colored);
+
+            let mut wrapped;
+            let line = match self.wrap_width {
+                Some(width) if self.log_format == LogFormat::Plain => {
+                    wrapped = wrap::wrap(&line[..msg_start], &line[msg_start..], width);
+                    &mut wrapped
+                }
+                _ => line,
+            };
+            line.push('\n');
+
+            let mut result = Ok(());
+            let mut write = || result = stream.write_line(line.as_bytes());
+
+            match &self.console_coordinator {
+                Some(coordinator) => console::coordinate(&*coordinator.0, &mut write),
+                None => write(),
+            }
+
+            Ok(result?)
+        })
+    }
+
+    /// Appends a log line of `level` to every file target whose level it reaches,
+    /// unless it's below `Logger::min_level`.
+    fn line_to_file<T: std::fmt::Display>(
+        &self,
+        level: Level,
+        prefix: &str,
+        msg: T,
+        fields: &[(&str, &str)],
+    ) -> Result<(), file::FileError<'_>> {
+        if self.targets.is_empty() {
+            return Err(file::FileError {
+                err: LoggerError::NoLogFile,
+                path: None,
+            });
+        }
+
+        if level < self.min_level() {
+            return Ok(());
+        }
+
+        // Rotating would rename the file away, and pruning could remove it.
+        if self.append_only && self.rotation.is_some() {
+            return Err(file::FileError {
+                err: LoggerError::AppendOnly,
+                path: self.file_log.as_deref(),
+            });
+        }
+
+        scratch::with_scratch(|line| {
+            line.push('\n');
+            self.write_line(line, level, prefix, msg, fields, false);
+
+            let options = file::WriteOptions {
+                rotation: self.rotation,
+                max_backups: self.max_backups,
+                append_only: self.append_only,
+                flush_policy: self.flush_policy,
+                use_utc: self.use_utc,
+            };
+
+            let targets = self
+                .targets
+                .iter()
+                .filter(|target| level >= target.min_level);
+
+            if let Some(background) = &self.background {
+                return background
+                    .push(line.as_bytes(), targets.cloned().collect(), options)
+                    .map_err(|err| file::FileError {
+                        err: err.into(),
+                        path: None,
+                    });
+            }
+
+            for target in targets {
+                target
+                    .write(line.as_bytes(), &options)
+                    .map_err(|err| file::FileError {
+                        err: err.into(),
+                        path: Some(&target.path),
+                    })?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Panics because of an error from a log file.
+    fn file_failed(&self, err: file::FileError) -> ! {
+        fail(err.err, "Could not write to log file", err.path)
+    }
 
-        println!("{}", to_log);
+    /// Flushes what was logged and exits the process with status 1.
+    fn stop(&self) -> ! {
+        self.try_flush().ok();
+        std::process::exit(1);
     }
 
-    /// Logs an error to file `Logger.file_log` (and not shown on the console).
+    /// Logs at `level` to the console, and to the file targets if there are any,
+    /// unless `level` is below `Logger::min_level`.
     /// # Example
     /// ```rust
-    /// use lawg::Logger;
+    /// use lawg::{Level, Logger};
     ///
-    /// let my_logger = Logger::new("My Logger", Some("../logs/log_file.txt"), true);
-    /// my_logger.error_to_file("Something went wrong! Try again later");
+    /// let my_logger = Logger::new(String::from("My Logger"), None, true);
+    /// my_logger.log_at(Level::Warn, "Running low on cereals"); // WARN: My Logger - ["yyyy-mm-dd hh:mm:ss UTC"]: Running low on cereals
     /// ```
-    pub fn error_to_file<T: std::fmt::Display>(&self, msg: T) {
-        if let Some(file_log) = &self.file_log {
-            let file_log_content = fs::read_to_string(file_log.clone())
-                .unwrap_or_else(|_| panic!("Could not read log file `{}`", file_log.clone()));
-
-            fs::write(
-                &file_log.clone(),
-                (file_log_content
-                    + "\n"
-                    + &format!(
-                        "ERROR: {} - [{:?}]: {}",
-                        self.logger_name,
-                        {
-                            if self.use_utc {
-                                Utc::now().to_string()
-                            } else {
-                                Local::now().to_string()
-                            }
-                        },
-                        msg
-                    ))
-                    .as_bytes(),
-            )
-            .unwrap_or_else(|_| panic!("Could not create log file `{}`", file_log.clone()));
-        } else {
-            panic!("Log file not provided.");
+    pub fn log_at<T: std::fmt::Display>(&self, level: Level, msg: T) {
+        self.line_to_console(level, level.prefix(), &msg, &[])
+            .unwrap_or_else(|err| fail(err, "Could not print to the console", None));
+
+        if !self.targets.is_empty() {
+            self.line_to_file(level, level.prefix(), msg, &[])
+                .unwrap_or_else(|err| self.file_failed(err));
+        }
+    }
+
+    /// Like `Logger::log_at`, but returns an error instead of panicking.
+    pub fn try_log_at<T: std::fmt::Display>(
+        &self,
+        level: Level,
+        msg: T,
+    ) -> Result<(), LoggerError> {
+        self.line_to_console(level, level.prefix(), &msg, &[])?;
+
+        if !self.targets.is_empty() {
+            self.line_to_file(level, level.prefix(), msg, &[])?;
         }
+
+        Ok(())
     }
 
-    /// Logs an error to the console and file `Logger.file_log`.
+    /// Logs at `Level::Info` like `Logger::info`, with extra key/value `fields`.
+    /// They're appended to the message as `key=value`, or become fields of the object with `LogFormat::Json`.
     /// # Example
     /// ```rust
     /// use lawg::Logger;
     ///
-    /// let my_logger = Logger::new("My Logger", Some("../logs/log_file.txt"), true);
-    /// my_logger.error_to_file("Something went wrong! Try again later");
+    /// let my_logger = Logger::new(String::from("My Logger"), None, true);
+    /// my_logger.log_with_fields("Request done", &[("status", "200"), ("ms", "41")]); // INFO: My Logger - ["yyyy-mm-dd hh:mm:ss UTC"]: Request done status=200 ms=41
     /// ```
-    pub fn error_and_error_to_file(&self, msg: String) {
-        self.error(msg.clone());
-        self.error_to_file(msg);
+    pub fn log_with_fields<T: std::fmt::Display>(&self, msg: T, fields: &[(&str, &str)]) {
+        self.line_to_console(Level::Info, Level::Info.prefix(), &msg, fields)
+            .unwrap_or_else(|err| fail(err, "Could not print to the console", None));
+
+        if !self.targets.is_empty() {
+            self.line_to_file(Level::Info, Level::Info.prefix(), msg, fields)
+                .unwrap_or_else(|err| self.file_failed(err));
+        }
     }
 
-    /// Logs an error to the console and stops the program.
-    pub fn error_and_stop<T: std::fmt::Display>(&self, msg: T) {
-        let to_log = format!(
-            "ERROR: {} - [{:?}]: {}",
-            self.logger_name,
-            {
-                if self.use_utc {
-                    Utc::now().to_string()
-                } else {
-                    Local::now().to_string()
-                }
-            },
-            msg
-        );
+    /// Like `Logger::log_with_fields`, but returns an error instead of panicking.
+    pub fn try_log_with_fields<T: std::fmt::Display>(
+        &self,
+        msg: T,
+        fields: &[(&str, &str)],
+    ) -> Result<(), LoggerError> {
+        self.line_to_console(Level::Info, Level::Info.prefix(), &msg, fields)?;
 
-        println!("{}", to_log);
+        if !self.targets.is_empty() {
+            self.line_to_file(Level::Info, Level::Info.prefix(), msg, fields)?;
+        }
 
-        std::process::exit(1);
+        Ok(())
     }
 
-    /// Logs an error to file `Logger.file_log` and stops the program.
-    pub fn error_and_stop_to_file<T: std::fmt::Display>(&self, msg: T) {
-        if let Some(file_log) = &self.file_log {
-            let file_log_content = fs::read_to_string(file_log.clone())
-                .unwrap_or_else(|_| panic!("Could not read log file `{}`", file_log.clone()));
-
-            fs::write(
-                &file_log.clone(),
-                (file_log_content
-                    + "\n"
-                    + &format!(
-                        "ERROR: {} - [{:?}]: {}",
-                        self.logger_name,
-                        {
-                            if self.use_utc {
-                                Utc::now().to_string()
-                            } else {
-                                Local::now().to_string()
-                            }
-                        },
-                        msg
-                    ))
-                    .as_bytes(),
-            )
-            .unwrap_or_else(|_| panic!("Could not create log file `{}`", file_log.clone()));
+    /// Logs at `Level::Info` like `Logger::info`, but only the first time this logger is given `key`.
+    /// # Example
+    /// ```rust
+    /// use lawg::Logger;
+    ///
+    /// let my_logger = Logger::new(String::from("My Logger"), None, true);
+    ///
+    /// for _ in 0..3 {
+    ///     my_logger.log_once("config", "No config file, using the defaults"); // Logged once
+    /// }
+    /// ```
+    pub fn log_once<T: std::fmt::Display>(&self, key: &str, msg: T) {
+        if let Some(msg) = self.unsuppressed(key, None, msg) {
+            self.log_at(Level::Info, msg);
+        }
+    }
 
-            std::process::exit(1);
-        } else {
-            panic!("Log file not provided.");
+    /// Like `Logger::log_once`, but returns an error instead of panicking.
+    pub fn try_log_once<T: std::fmt::Display>(&self, key: &str, msg: T) -> Result<(), LoggerError> {
+        match self.unsuppressed(key, None, msg) {
+            Some(msg) => self.try_log_at(Level::Info, msg),
+            None => Ok(()),
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn it_works() {
-        use crate::Logger;
+    /// Logs at `Level::Info` like `Logger::info`, but at most once per `interval` for each `key`.
+    /// The next message logged for a key ends with ` (suppressed n similar messages)` if any were left out.
+    /// # Example
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use lawg::Logger;
+    ///
+    /// let my_logger = Logger::new(String::from("My Logger"), Some(String::from("../logs/log_file.txt")), true);
+    ///
+    /// loop {
+    ///     // INFO: My Logger - ["yyyy-mm-dd hh:mm:ss UTC"]: Connection refused, retrying (suppressed 314 similar messages)
+    ///     my_logger.log_every("connect", Duration::from_secs(10), "Connection refused, retrying");
+    /// }
+    /// ```
+    pub fn log_every<T: std::fmt::Display>(&self, key: &str, interval: Duration, msg: T) {
+        if let Some(msg) = self.unsuppressed(key, Some(interval), msg) {
+            self.log_at(Level::Info, msg);
+        }
+    }
 
-        let my_logger = Logger::new("My Logger".to_string(), Some("log.txt".to_string()), true);
+    /// Like `Logger::log_every`, but returns an error instead of panicking.
+    pub fn try_log_every<T: std::fmt::Display>(
+        &self,
+        key: &str,
+        interval: Duration,
+        msg: T,
+    ) -> Result<(), LoggerError> {
+        match self.unsuppressed(key, Some(interval), msg) {
+            Some(msg) => self.try_log_at(Level::Info, msg),
+            None => Ok(()),
+        }
+    }
 
-        my_logger.log("Hello world");
-        my_logger.log_to_file("Hello world 123");
-        my_logger.log_and_to_file("He ate my cereals");
+    /// `msg` with the count of suppressed messages, if a message for `key` may be logged now.
+    /// Messages below `Logger::min_level` aren't logged anyway, so they don't count.
+    fn unsuppressed<T: std::fmt::Display>(
+        &self,
+        key: &str,
+        interval: Option<Duration>,
+        msg: T,
+    ) -> Option<suppress::WithSuppressed<T>> {
+        if Level::Info < self.min_level() {
+            return None;
+        }
+
+        let suppressed = self.suppressor.allow(key, interval)?;
+
+        Some(suppress::WithSuppressed { msg, suppressed })
+    }
+
+    /// Logs at `Level::Trace`, see `Logger::log_at`.
+    pub fn trace<T: std::fmt::Display>(&self, msg: T) {
+        self.log_at(Level::Trace, msg);
+    }
+
+    /// Logs at `Level::Debug`, see `Logger::log_at`.
+    pub fn debug<T: std::fmt::Display>(&self, msg: T) {
+        self.log_at(Level::Debug, msg);
+    }
+
+    /// Logs at `Level::Info`, see `Logger::log_at`.
+    /// Unlike `Logger::log`, the line starts with `INFO: ` and is also written to the file.
+    pub fn info<T: std::fmt::Display>(&self, msg: T) {
+        self.log_at(Level::Info, msg);
+    }
+
+    /// Logs at `Level::Warn`, see `Logger::log_at`.
+    pub fn warn<T: std::fmt::Display>(&self, msg: T) {
+        self.log_at(Level::Warn, msg);
+    }
+
+    /// Logs to the console.
+    /// # Example
+    /// ```rust,no_run
+    /// use lawg::Logger;
+    ///
+    /// let my_logger = Logger::new(String::from("My Logger"), Some(String::from("../logs/log_file.txt")), true);
+    /// my_logger.log("This is a log"); // My Logger - ["yyyy-mm-dd hh:mm:ss UTC"]: This is a log
+    /// ```
+    pub fn log<T: std::fmt::Display>(&self, msg: T) {
+        self.line_to_console(Level::Info, "", msg, &[])
+            .unwrap_or_else(|err| fail(err, "Could not print to the console", None));
+    }
+
+    /// Logs to the file targets (and not shown on the console).
+    /// # Example
+    /// ```rust,no_run
+    /// use lawg::Logger;
+    ///
+    /// let my_logger = Logger::new(String::from("My Logger"), Some(String::from("../logs/log_file.txt")), true);
+    /// my_logger.log_to_file("This is log is written on the file and not shown on the console.");
+    /// ```
+    pub fn log_to_file<T: std::fmt::Display>(&self, msg: T) {
+        self.line_to_file(Level::Info, "", msg, &[])
+            .unwrap_or_else(|err| self.file_failed(err));
+    }
+
+    /// Like `Logger::log_to_file`, but returns an error instead of panicking.
+    pub fn try_log_to_file<T: std::fmt::Display>(&self, msg: T) -> Result<(), LoggerError> {
+        Ok(self.line_to_file(Level::Info, "", msg, &[])?)
+    }
+
+    /// Logs to the console and the file targets.
+    /// # Example
+    /// ```rust,no_run
+    /// use lawg::Logger;
+    ///
+    /// let my_logger = Logger::new(String::from("My Logger"), Some(String::from("../logs/log_file.txt")), true);
+    /// my_logger.log_and_log_to_file(String::from("This log will appear on the console and also be written to the file")); // My Logger - ["yyyy-mm-dd hh:mm:ss UTC"]: This log will appear on the console and also be written to the file
+    /// ```
+    pub fn log_and_log_to_file(&self, msg: String) {
+        self.log(&msg);
+        self.log_to_file(msg);
+    }
+
+    /// Like `Logger::log_and_log_to_file`, but returns an error instead of panicking.
+    pub fn try_log_and_log_to_file(&self, msg: String) -> Result<(), LoggerError> {
+        self.line_to_console(Level::Info, "", &msg, &[])?;
+        Ok(self.line_to_file(Level::Info, "", msg, &[])?)
+    }
+
+    /// Logs an error to the console, on stderr unless `Logger.console_target` says otherwise.
+    /// # Example
+    /// ```rust,no_run
+    /// use lawg::Logger;
+    ///
+    /// let my_logger = Logger::new(String::from("My Logger"), Some(String::from("../logs/log_file.txt")), true);
+    /// my_logger.error("Something went wrong! Try again later"); // ERROR: My Logger - ["yyyy-mm-dd hh:mm:ss UTC"]: Something went wrong! Try again later
+    /// ```
+    pub fn error<T: std::fmt::Display>(&self, msg: T) {
+        self.line_to_console(Level::Error, Level::Error.prefix(), msg, &[])
+            .unwrap_or_else(|err| fail(err, "Could not print to the console", None));
+    }
+
+    /// Logs an error to the file targets (and not shown on the console).
+    /// # Example
+    /// ```rust,no_run
+    /// use lawg::Logger;
+    ///
+    /// let my_logger = Logger::new(String::from("My Logger"), Some(String::from("../logs/log_file.txt")), true);
+    /// my_logger.error_to_file("Something went wrong! Try again later");
+    /// ```
+    pub fn error_to_file<T: std::fmt::Display>(&self, msg: T) {
+        self.line_to_file(Level::Error, Level::Error.prefix(), msg, &[])
+            .unwrap_or_else(|err| self.file_failed(err));
+    }
+
+    /// Like `Logger::error_to_file`, but returns an error instead of panicking.
+    pub fn try_error_to_file<T: std::fmt::Display>(&self, msg: T) -> Result<(), LoggerError> {
+        Ok(self.line_to_file(Level::Error, Level::Error.prefix(), msg, &[])?)
+    }
+
+    /// Logs an error to the console and the file targets.
+    /// # Example
+    /// ```rust,no_run
+    /// use lawg::Logger;
+    ///
+    /// let my_logger = Logger::new(String::from("My Logger"), Some(String::from("../logs/log_file.txt")), true);
+    /// my_logger.error_to_file("Something went wrong! Try again later");
+    /// ```
+    pub fn error_and_error_to_file(&self, msg: String) {
+        self.error(&msg);
+        self.error_to_file(msg);
+    }
+
+    /// Like `Logger::error_and_error_to_file`, but returns an error instead of panicking.
+    pub fn try_error_and_error_to_file(&self, msg: String) -> Result<(), LoggerError> {
+        self.line_to_console(Level::Error, Level::Error.prefix(), &msg, &[])?;
+        Ok(self.line_to_file(Level::Error, Level::Error.prefix(), msg, &[])?)
+    }
+
+    /// Logs an error to the console and stops the program.
+    pub fn error_and_stop<T: std::fmt::Display>(&self, msg: T) {
+        self.error(msg);
+
+        self.stop();
+    }
+
+    /// Logs an error to the console like `Logger::error_and_stop`, but returns instead of stopping the program,
+    /// so the caller can decide how to stop.
+    pub fn try_error_and_stop<T: std::fmt::Display>(&self, msg: T) -> Result<(), LoggerError> {
+        self.line_to_console(Level::Error, Level::Error.prefix(), msg, &[])
+    }
+
+    /// Logs an error to the file targets and stops the program.
+    /// The error is also logged to the console first, unless `Logger.echo_fatal_to_console` is `false`.
+    pub fn error_and_stop_to_file<T: std::fmt::Display>(&self, msg: T) {
+        self.error_to_file(&msg);
+
+        if self.echo_fatal_to_console {
+            self.error(&msg);
+        }
+
+        self.stop();
+    }
+
+    /// Logs an error like `Logger::error_and_stop_to_file`, but returns instead of stopping the program,
+    /// so the caller can decide how to stop.
+    pub fn try_error_and_stop_to_file<T: std::fmt::Display>(
+        &self,
+        msg: T,
+    ) -> Result<(), LoggerError> {
+        self.line_to_file(Level::Error, Level::Error.prefix(), &msg, &[])?;
+
+        if self.echo_fatal_to_console {
+            self.line_to_console(Level::Error, Level::Error.prefix(), &msg, &[])?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Panics because of `err`, for the methods that don't return a `Result`.
+/// I/O errors are described with `context` and the log file's `path`.
+fn fail(err: LoggerError, context: &str, path: Option<&str>) -> ! {
+    match (err, path) {
+        (LoggerError::Io(err), Some(path)) => panic!("{} `{}`: {}", context, path, err),
+        (LoggerError::Io(err), None) => panic!("{}: {}", context, err),
+        (err, _) => panic!("{}", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn it_works() {
+        use crate::Logger;
+
+        let file = std::env::temp_dir().join(format!("lawg-it-works-{}.txt", std::process::id()));
+        let my_logger = Logger::new(
+            "My Logger".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            true,
+        );
+
+        my_logger.log("Hello world");
+        my_logger.log_to_file("Hello world 123");
+        my_logger.log_and_log_to_file("He ate my cereals".to_string());
+
+        drop(my_logger);
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn file_lines_keep_their_format() {
+        use crate::Logger;
+
+        let file = std::env::temp_dir().join(format!("lawg-format-{}.txt", std::process::id()));
+        let my_logger = Logger::new(
+            "My Logger".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            true,
+        );
+
+        my_logger.log_to_file("Hello world");
+        my_logger.error_to_file("Cereals are gone");
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        // Every line is preceded by a newline, so the file starts with an empty line.
+        let lines: Vec<&str> = content.split('\n').collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "");
+        assert!(lines[1].starts_with("My Logger - [\""));
+        assert!(lines[1].ends_with(" UTC\"]: Hello world"));
+        assert!(lines[2].starts_with("ERROR: My Logger - [\""));
+        assert!(lines[2].ends_with(" UTC\"]: Cereals are gone"));
+    }
+
+    #[test]
+    fn file_keeps_every_line_in_order() {
+        use crate::Logger;
+
+        let file = std::env::temp_dir().join(format!("lawg-order-{}.txt", std::process::id()));
+        let my_logger = Logger::new(
+            "My Logger".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            true,
+        );
+
+        for i in 0..10_000 {
+            my_logger.log_to_file(i);
+        }
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        let numbers: Vec<usize> = content
+            .lines()
+            .skip(1)
+            .map(|line| line.split_once("]: ").unwrap().1.parse().unwrap())
+            .collect();
+        assert_eq!(numbers, (0..10_000).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn loggers_sharing_a_file_keep_each_others_lines() {
+        use crate::Logger;
+
+        let file = std::env::temp_dir().join(format!("lawg-shared-{}.txt", std::process::id()));
+        let path = file.to_str().unwrap().to_string();
+        let first = Logger::new("First".to_string(), Some(path.clone()), true);
+        let second = Logger::new("Second".to_string(), Some(path), true);
+
+        for i in 0..100 {
+            first.log_to_file(i);
+            second.log_to_file(i);
+        }
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        assert_eq!(content.matches("First - [").count(), 100);
+        assert_eq!(content.matches("Second - [").count(), 100);
+    }
+
+    #[test]
+    fn logger_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<crate::Logger>();
+    }
+
+    /// Checks that `content` holds exactly the lines `"{thread}-{i}"` for 8 threads and 1000 `i`s.
+    fn assert_every_thread_line(content: &str) {
+        let mut lines: Vec<&str> = content
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| line.split_once("]: ").unwrap().1)
+            .collect();
+        assert_eq!(lines.len(), 8000);
+
+        lines.sort_unstable();
+        lines.dedup();
+        assert_eq!(lines.len(), 8000);
+
+        for line in lines {
+            let (thread, i) = line.split_once('-').unwrap();
+            assert!(thread.parse::<usize>().unwrap() < 8);
+            assert!(i.parse::<usize>().unwrap() < 1000);
+        }
+    }
+
+    #[test]
+    fn threads_sharing_a_logger_keep_every_line() {
+        use crate::Logger;
+        use std::sync::Arc;
+
+        let file = std::env::temp_dir().join(format!("lawg-threads-{}.txt", std::process::id()));
+        let my_logger = Arc::new(Logger::new(
+            "My Logger".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            true,
+        ));
+
+        let threads: Vec<_> = (0..8)
+            .map(|thread| {
+                let my_logger = Arc::clone(&my_logger);
+                std::thread::spawn(move || {
+                    for i in 0..1000 {
+                        my_logger.log_to_file(format!("{}-{}", thread, i));
+                    }
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        assert_every_thread_line(&content);
+    }
+
+    #[test]
+    fn threads_with_loggers_on_one_file_keep_every_line() {
+        use crate::Logger;
+
+        let file =
+            std::env::temp_dir().join(format!("lawg-threads-shared-{}.txt", std::process::id()));
+        let path = file.to_str().unwrap().to_string();
+        std::fs::write(&file, "").unwrap();
+
+        let threads: Vec<_> = (0..8)
+            .map(|thread| {
+                let my_logger =
+                    Logger::new_append_only("My Logger".to_string(), path.clone(), true);
+                std::thread::spawn(move || {
+                    for i in 0..1000 {
+                        my_logger.log_to_file(format!("{}-{}", thread, i));
+                    }
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        assert_every_thread_line(&content);
+    }
+
+    #[test]
+    fn append_only_logger_keeps_existing_content() {
+        use crate::Logger;
+
+        let file =
+            std::env::temp_dir().join(format!("lawg-append-only-{}.txt", std::process::id()));
+        std::fs::write(&file, "Earlier entry").unwrap();
+
+        let my_logger = Logger::new_append_only(
+            "My Logger".to_string(),
+            file.to_str().unwrap().to_string(),
+            true,
+        );
+        my_logger.log_to_file("Hello world");
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        assert!(content.starts_with("Earlier entry\nMy Logger - ["));
+        assert!(content.ends_with("]: Hello world"));
+    }
+
+    #[test]
+    fn append_only_logger_refuses_rotation_and_pruning() {
+        use crate::{LoggerError, RotationPolicy};
+
+        let file = std::env::temp_dir().join(format!(
+            "lawg-append-only-rotation-{}.txt",
+            std::process::id()
+        ));
+        std::fs::write(&file, "Earlier entry").unwrap();
+
+        let mut my_logger = crate::Logger::new_append_only(
+            "My Logger".to_string(),
+            file.to_str().unwrap().to_string(),
+            true,
+        );
+        my_logger.rotation = Some(RotationPolicy::MaxSize(10));
+        my_logger.max_backups = Some(0);
+
+        // Rotating a file past `MaxSize` with no backups kept would remove it.
+        assert!(matches!(
+            my_logger.try_log_to_file("Hello world"),
+            Err(LoggerError::AppendOnly)
+        ));
+        my_logger.set_async(16, crate::OverflowPolicy::Block);
+        assert!(matches!(
+            my_logger.try_error_to_file("Hello again"),
+            Err(LoggerError::AppendOnly)
+        ));
+        drop(my_logger);
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        assert_eq!(content, "Earlier entry");
+        assert!(!std::path::Path::new(&format!("{}.1", file.display())).exists());
+    }
+
+    #[test]
+    fn missing_parent_directories_are_created() {
+        use crate::Logger;
+
+        let dir = std::env::temp_dir().join(format!("lawg-parents-{}", std::process::id()));
+        let file = dir.join("logs").join("general").join("logs.txt");
+
+        let my_logger = Logger::new(
+            "My Logger".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            true,
+        );
+        my_logger.log_to_file("Hello world");
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(content.ends_with("]: Hello world"));
+    }
+
+    #[test]
+    fn parents_that_are_files_are_errors() {
+        use crate::{Logger, LoggerError};
+
+        let file = std::env::temp_dir().join(format!("lawg-parent-file-{}", std::process::id()));
+        std::fs::write(&file, "").unwrap();
+
+        let result = Logger::try_new(
+            "My Logger".to_string(),
+            Some(file.join("logs.txt").to_str().unwrap().to_string()),
+            true,
+        );
+        std::fs::remove_file(&file).unwrap();
+
+        assert!(matches!(result, Err(LoggerError::Io(_))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_only_parents_are_errors() {
+        use crate::{Logger, LoggerError};
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("lawg-read-only-{}", std::process::id()));
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o555)).unwrap();
+
+        // Permissions don't stop root, so there's nothing to check then.
+        let writable = std::fs::create_dir(dir.join("probe")).is_ok();
+
+        let result = Logger::try_new(
+            "My Logger".to_string(),
+            Some(
+                dir.join("logs")
+                    .join("logs.txt")
+                    .to_str()
+                    .unwrap()
+                    .to_string(),
+            ),
+            true,
+        );
+
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        if !writable {
+            match result {
+                Err(LoggerError::Io(err)) => {
+                    assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied)
+                }
+                _ => panic!("expected a permission error"),
+            }
+        }
+    }
+
+    #[test]
+    fn relative_paths_are_opened() {
+        use crate::Logger;
+
+        let file = format!("target/lawg-relative-{}/logs.txt", std::process::id());
+        let my_logger = Logger::new("My Logger".to_string(), Some(file.clone()), true);
+        my_logger.log_to_file("Hello world");
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_dir_all(std::path::Path::new(&file).parent().unwrap()).unwrap();
+
+        assert!(content.ends_with("]: Hello world"));
+    }
+
+    #[test]
+    fn try_methods_return_errors() {
+        use crate::{Logger, LoggerError};
+
+        let my_logger = Logger::try_new("My Logger".to_string(), None, true).unwrap();
+        assert!(matches!(
+            my_logger.try_log_to_file("Hello world"),
+            Err(LoggerError::NoLogFile)
+        ));
+        assert!(matches!(
+            my_logger.try_error_and_stop_to_file("Cereals are gone"),
+            Err(LoggerError::NoLogFile)
+        ));
+
+        // A directory can't be opened as a log file.
+        let dir = std::env::temp_dir().to_str().unwrap().to_string();
+        assert!(matches!(
+            Logger::try_new("My Logger".to_string(), Some(dir), true),
+            Err(LoggerError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn try_error_and_stop_to_file_does_not_stop() {
+        use crate::Logger;
+
+        let file = std::env::temp_dir().join(format!("lawg-try-stop-{}.txt", std::process::id()));
+        let mut my_logger = Logger::try_new(
+            "My Logger".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            true,
+        )
+        .unwrap();
+        my_logger.echo_fatal_to_console = false;
+
+        my_logger
+            .try_error_and_stop_to_file("Cereals are gone")
+            .unwrap();
+        my_logger.try_error_and_stop("Still running").unwrap();
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        assert!(content.ends_with("]: Cereals are gone"));
+    }
+
+    #[test]
+    fn min_level_filters_file_lines() {
+        use crate::{Level, Logger};
+
+        let file = std::env::temp_dir().join(format!("lawg-min-level-{}.txt", std::process::id()));
+        let my_logger = Logger::new(
+            "My Logger".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            true,
+        );
+        my_logger.set_min_level(Level::Warn);
+        assert_eq!(my_logger.min_level(), Level::Warn);
+
+        my_logger.info("Dropped");
+        my_logger.log_to_file("Dropped too");
+        assert_eq!(std::fs::metadata(&file).unwrap().len(), 0);
+
+        my_logger.warn("Running low on cereals");
+        my_logger.error_to_file("Cereals are gone");
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        let lines: Vec<&str> = content.lines().skip(1).collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("WARN: My Logger - ["));
+        assert!(lines[0].ends_with("]: Running low on cereals"));
+        assert!(lines[1].starts_with("ERROR: My Logger - ["));
+    }
+
+    #[test]
+    fn custom_format_applies_to_file_lines() {
+        use crate::Logger;
+
+        let file =
+            std::env::temp_dir().join(format!("lawg-custom-format-{}.txt", std::process::id()));
+        let mut my_logger = Logger::new(
+            "My Logger".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            true,
+        );
+        my_logger
+            .set_format("{timestamp} [{level}] {name}: {message}")
+            .unwrap();
+        my_logger.set_time_format("%Y-%m-%dT%H:%M:%S%.3f").unwrap();
+
+        my_logger.log_to_file("Hello world");
+        my_logger.error_to_file("Cereals are gone");
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        let lines: Vec<&str> = content.lines().skip(1).collect();
+        assert_eq!(lines.len(), 2);
+
+        let (timestamp, rest) = lines[0].split_once(' ').unwrap();
+        assert_eq!(timestamp.len(), "yyyy-mm-ddThh:mm:ss.sss".len());
+        assert_eq!(&timestamp[10..11], "T");
+        assert_eq!(rest, "[INFO] My Logger: Hello world");
+        assert!(lines[1].ends_with(" [ERROR] My Logger: Cereals are gone"));
+    }
+
+    #[test]
+    fn invalid_formats_are_rejected() {
+        use crate::{Logger, LoggerError};
+
+        let mut my_logger = Logger::new("My Logger".to_string(), None, true);
+
+        assert!(matches!(
+            my_logger.set_format("{name}: {msg}"),
+            Err(LoggerError::InvalidFormat(_))
+        ));
+        assert!(matches!(
+            my_logger.set_time_format("%Q"),
+            Err(LoggerError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn size_rotation_splits_the_file() {
+        use crate::{Logger, RotationPolicy};
+
+        let file = std::env::temp_dir().join(format!("lawg-rotate-{}.txt", std::process::id()));
+        let path = file.to_str().unwrap().to_string();
+        let rotated = format!("{}.1", path);
+
+        let mut my_logger = Logger::new("My Logger".to_string(), Some(path.clone()), true);
+        my_logger.set_format("{name}: {message}").unwrap();
+        my_logger.rotation = Some(RotationPolicy::MaxSize(64));
+
+        // Every entry is "\nMy Logger: line n", 19 bytes, so three fit in 64 bytes.
+        for i in 0..5 {
+            my_logger.log_to_file(format!("line {}", i));
+        }
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        let rotated_content = std::fs::read_to_string(&rotated).unwrap();
+        std::fs::remove_file(&file).unwrap();
+        std::fs::remove_file(&rotated).unwrap();
+
+        assert_eq!(
+            rotated_content,
+            "\nMy Logger: line 0\nMy Logger: line 1\nMy Logger: line 2"
+        );
+        assert_eq!(content, "\nMy Logger: line 3\nMy Logger: line 4");
+    }
+
+    #[test]
+    fn size_rotation_keeps_max_backups() {
+        use crate::{Logger, RotationPolicy};
+
+        let dir = std::env::temp_dir().join(format!("lawg-backups-{}", std::process::id()));
+        std::fs::create_dir(&dir).unwrap();
+        let path = dir.join("logs.txt").to_str().unwrap().to_string();
+
+        let mut my_logger = Logger::new("My Logger".to_string(), Some(path.clone()), true);
+        my_logger.set_format("{message}").unwrap();
+        my_logger.rotation = Some(RotationPolicy::MaxSize(1));
+        my_logger.max_backups = Some(2);
+
+        // Each entry gets a file of its own.
+        for i in 0..5 {
+            my_logger.log_to_file(i);
+        }
+
+        let mut names: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["logs.txt", "logs.txt.1", "logs.txt.2"]);
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "\n4");
+        assert_eq!(
+            std::fs::read_to_string(format!("{}.1", path)).unwrap(),
+            "\n3"
+        );
+        assert_eq!(
+            std::fs::read_to_string(format!("{}.2", path)).unwrap(),
+            "\n2"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn daily_rotation_dates_yesterdays_file() {
+        use crate::{Logger, RotationPolicy};
+        use std::time::{Duration, SystemTime};
+
+        let file = std::env::temp_dir().join(format!("lawg-daily-{}.txt", std::process::id()));
+        let path = file.to_str().unwrap().to_string();
+
+        let yesterday = SystemTime::now() - Duration::from_secs(24 * 60 * 60);
+        std::fs::write(&file, "\nYesterday's entry").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_modified(yesterday)
+            .unwrap();
+
+        let mut my_logger = Logger::new("My Logger".to_string(), Some(path.clone()), true);
+        my_logger.set_format("{message}").unwrap();
+        my_logger.rotation = Some(RotationPolicy::Daily);
+        my_logger.log_to_file("Today's entry");
+
+        let (year, month, day) = crate::time::date(yesterday, true);
+        let dated = format!("{}.{:04}-{:02}-{:02}", path, year, month, day);
+
+        assert_eq!(
+            std::fs::read_to_string(&dated).unwrap(),
+            "\nYesterday's entry"
+        );
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "\nToday's entry");
+
+        std::fs::remove_file(&file).unwrap();
+        std::fs::remove_file(&dated).unwrap();
+    }
+
+    #[test]
+    fn json_lines_round_trip() {
+        use crate::{LogFormat, Logger};
+
+        let file = std::env::temp_dir().join(format!("lawg-json-{}.txt", std::process::id()));
+        let mut my_logger = Logger::new(
+            "My \"Logger\"".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            true,
+        );
+        my_logger.log_format = LogFormat::Json;
+
+        let message = "He said \"no\"\nthen left \\ ünïcödé 日本 \u{7}";
+        my_logger.log_with_fields(message, &[("status", "200"), ("path", "/a \"b\"")]);
+        my_logger.error_to_file("Cereals are gone");
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .skip(1)
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+
+        assert_eq!(lines[0]["logger"], "My \"Logger\"");
+        assert_eq!(lines[0]["level"], "INFO");
+        assert_eq!(lines[0]["message"], message);
+        assert_eq!(lines[0]["status"], "200");
+        assert_eq!(lines[0]["path"], "/a \"b\"");
+        assert_eq!(lines[1]["level"], "ERROR");
+        assert_eq!(lines[1]["message"], "Cereals are gone");
+
+        #[cfg(feature = "chrono")]
+        chrono::DateTime::parse_from_rfc3339(lines[0]["timestamp"].as_str().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn json_fields_do_not_replace_built_in_fields() {
+        use crate::{LogFormat, Logger};
+
+        let file =
+            std::env::temp_dir().join(format!("lawg-json-fields-{}.txt", std::process::id()));
+        let mut my_logger = Logger::new(
+            "My Logger".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            true,
+        );
+        my_logger.log_format = LogFormat::Json;
+
+        my_logger.log_with_fields(
+            "Request done",
+            &[
+                ("level", "custom"),
+                ("logger", "other"),
+                ("timestamp", "now"),
+                ("message", "shadow"),
+                ("status", "200"),
+            ],
+        );
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        let line = content.trim_start();
+        for key in ["logger", "level", "timestamp", "message"] {
+            assert_eq!(
+                line.matches(&format!("\"{}\":", key)).count(),
+                1,
+                "{}",
+                line
+            );
+        }
+
+        let line: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["logger"], "My Logger");
+        assert_eq!(line["message"], "Request done");
+        assert_eq!(line["fields.level"], "custom");
+        assert_eq!(line["fields.logger"], "other");
+        assert_eq!(line["fields.timestamp"], "now");
+        assert_eq!(line["fields.message"], "shadow");
+        assert_eq!(line["status"], "200");
+    }
+
+    #[test]
+    fn plain_lines_end_with_fields() {
+        use crate::Logger;
+
+        let file = std::env::temp_dir().join(format!("lawg-fields-{}.txt", std::process::id()));
+        let mut my_logger = Logger::new(
+            "My Logger".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            true,
+        );
+        my_logger.set_format("{name}: {message}").unwrap();
+
+        my_logger.log_with_fields("Request done", &[("status", "200"), ("path", "/a b")]);
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        assert_eq!(
+            content,
+            "\nMy Logger: Request done status=200 path=\"/a b\""
+        );
+    }
+
+    /// A console writer whose output stays readable after it's given to a `Logger`.
+    #[derive(Clone, Default)]
+    struct Captured(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl Captured {
+        fn take(&self) -> String {
+            String::from_utf8(std::mem::take(&mut *self.0.lock().unwrap())).unwrap()
+        }
+    }
+
+    impl std::io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn console_target_picks_the_stream() {
+        use crate::{ConsoleTarget, Logger};
+
+        let (out, err) = (Captured::default(), Captured::default());
+        let mut my_logger = Logger::new("My Logger".to_string(), None, true);
+        my_logger.set_format("{level} {message}").unwrap();
+        my_logger.set_console_writers(out.clone(), err.clone());
+
+        let log_all = |my_logger: &Logger| {
+            my_logger.log("Hello world");
+            my_logger.warn("Running low on cereals");
+            my_logger.error("Cereals are gone");
+        };
+
+        log_all(&my_logger);
+        assert_eq!(
+            out.take(),
+            "INFO Hello world\nWARN Running low on cereals\n"
+        );
+        assert_eq!(err.take(), "ERROR Cereals are gone\n");
+
+        my_logger.console_target = ConsoleTarget::Stdout;
+        log_all(&my_logger);
+        assert_eq!(out.take().lines().count(), 3);
+        assert_eq!(err.take(), "");
+
+        my_logger.console_target = ConsoleTarget::Stderr;
+        log_all(&my_logger);
+        assert_eq!(out.take(), "");
+        assert_eq!(err.take().lines().count(), 3);
+    }
+
+    #[test]
+    fn always_colors_levels_and_names() {
+        use crate::{ColorMode, ConsoleTarget, Logger};
+
+        let out = Captured::default();
+        let mut my_logger = Logger::new("My Logger".to_string(), None, true);
+        my_logger.set_console_writers(out.clone(), out.clone());
+        my_logger.console_target = ConsoleTarget::Stdout;
+        my_logger.color_mode = ColorMode::Always;
+        // A time format without specifiers keeps the line predictable.
+        my_logger.set_time_format("now").unwrap();
+
+        my_logger.error("Cereals are gone");
+        assert_eq!(
+            out.take(),
+            "\x1b[31mERROR:\x1b[0m \x1b[1mMy Logger\x1b[0m - [\"now\"]: Cereals are gone\n"
+        );
+
+        my_logger.set_format("{level} {name}: {message}").unwrap();
+        my_logger.warn("Running low on cereals");
+        my_logger.info("Hello world");
+        assert_eq!(
+            out.take(),
+            "\x1b[33mWARN\x1b[0m \x1b[1mMy Logger\x1b[0m: Running low on cereals\n\
+             INFO \x1b[1mMy Logger\x1b[0m: Hello world\n"
+        );
+    }
+
+    #[test]
+    fn colors_stay_out_of_the_file() {
+        use crate::{ColorMode, Logger};
+
+        let file = std::env::temp_dir().join(format!("lawg-colors-{}.txt", std::process::id()));
+        let mut my_logger = Logger::new(
+            "My Logger".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            true,
+        );
+        my_logger.set_console_writers(Captured::default(), Captured::default());
+        my_logger.color_mode = ColorMode::Always;
+
+        my_logger.error_and_error_to_file("Cereals are gone".to_string());
+        my_logger.warn("Running low on cereals");
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        assert!(content.contains("ERROR: My Logger - "));
+        assert!(!content.contains('\x1b'));
+    }
+
+    #[test]
+    fn auto_does_not_color_captured_output() {
+        use crate::{ColorMode, Logger};
+
+        let err = Captured::default();
+        let mut my_logger = Logger::new("My Logger".to_string(), None, true);
+        my_logger.set_console_writers(Captured::default(), err.clone());
+        my_logger.color_mode = ColorMode::Auto;
+
+        my_logger.error("Cereals are gone");
+        assert!(!err.take().contains('\x1b'));
+    }
+
+    #[test]
+    fn console_lines_wrap_and_file_lines_do_not() {
+        use crate::Logger;
+
+        let file = std::env::temp_dir().join(format!("lawg-wrap-{}.txt", std::process::id()));
+        let stdout = Captured::default();
+        let mut my_logger = Logger::new(
+            "app".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            true,
+        );
+        my_logger.set_format("{name}: {message}").unwrap();
+        my_logger.set_console_writers(stdout.clone(), Captured::default());
+        my_logger.wrap_width = Some(20);
+
+        my_logger.log_and_log_to_file("the quick brown fox jumps over the lazy dog".to_string());
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        assert_eq!(
+            stdout.take(),
+            "app: the quick brown\n     fox jumps over\n     the lazy dog\n"
+        );
+        assert_eq!(
+            content,
+            "\napp: the quick brown fox jumps over the lazy dog"
+        );
+    }
+
+    #[test]
+    fn file_targets_get_the_levels_they_ask_for() {
+        use crate::{Level, Logger};
+
+        let temp = std::env::temp_dir();
+        let app = temp.join(format!("lawg-targets-app-{}.txt", std::process::id()));
+        let errors = temp.join(format!("lawg-targets-errors-{}.txt", std::process::id()));
+
+        let mut my_logger = Logger::new(
+            "My Logger".to_string(),
+            Some(app.to_str().unwrap().to_string()),
+            true,
+        );
+        my_logger.set_console_writers(Captured::default(), Captured::default());
+        my_logger.add_file_target("errors", errors.to_str().unwrap(), Level::Error);
+
+        my_logger.error_and_error_to_file("Cereals are gone".to_string());
+        my_logger.log_to_file("Hello world");
+
+        let app_content = std::fs::read_to_string(&app).unwrap();
+        let errors_content = std::fs::read_to_string(&errors).unwrap();
+
+        assert_eq!(app_content.lines().count(), 3);
+        assert!(app_content.contains("ERROR: My Logger - "));
+        assert!(app_content.ends_with("]: Hello world"));
+        assert_eq!(errors_content.lines().count(), 2);
+        assert!(errors_content.ends_with("]: Cereals are gone"));
+
+        assert!(my_logger.remove_file_target("errors"));
+        assert!(!my_logger.remove_file_target("errors"));
+        my_logger.error_to_file("Cereals are still gone");
+        assert_eq!(std::fs::read_to_string(&errors).unwrap(), errors_content);
+
+        assert!(my_logger.remove_file_target("default"));
+        assert!(matches!(
+            my_logger.try_log_to_file("Hello world"),
+            Err(crate::LoggerError::NoLogFile)
+        ));
+
+        std::fs::remove_file(&app).unwrap();
+        std::fs::remove_file(&errors).unwrap();
+    }
+
+    #[test]
+    fn flushing_every_n_lines_beats_every_line() {
+        use crate::{FlushPolicy, Logger};
+        use std::time::{Duration, Instant};
+
+        let time_lines = |flush_policy: FlushPolicy| -> Duration {
+            let file = std::env::temp_dir().join(format!(
+                "lawg-flush-{:?}-{}.txt",
+                flush_policy,
+                std::process::id()
+            ));
+            let mut my_logger = Logger::new(
+                "My Logger".to_string(),
+                Some(file.to_str().unwrap().to_string()),
+                true,
+            );
+            my_logger.flush_policy = flush_policy;
+
+            let start = Instant::now();
+            for i in 0..20_000 {
+                my_logger.log_to_file(i);
+            }
+            drop(my_logger);
+            let elapsed = start.elapsed();
+
+            let content = std::fs::read_to_string(&file).unwrap();
+            std::fs::remove_file(&file).unwrap();
+            assert_eq!(content.lines().count(), 20_001);
+
+            elapsed
+        };
+
+        // The fastest of a few runs each, so other tests running at the same time don't decide it.
+        let (mut every_line, mut every_1000) = (Duration::MAX, Duration::MAX);
+        for _ in 0..5 {
+            every_line = every_line.min(time_lines(FlushPolicy::EveryLine));
+            every_1000 = every_1000.min(time_lines(FlushPolicy::EveryN(1000)));
+        }
+        assert!(
+            every_1000 < every_line,
+            "EveryLine: {:?}, EveryN(1000): {:?}",
+            every_line,
+            every_1000
+        );
+    }
+
+    #[test]
+    fn last_line_survives_drop() {
+        use crate::{FlushPolicy, Logger};
+
+        let file = std::env::temp_dir().join(format!("lawg-drop-{}.txt", std::process::id()));
+        let mut my_logger = Logger::new(
+            "My Logger".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            true,
+        );
+        my_logger.flush_policy = FlushPolicy::Manual;
+
+        my_logger.log_to_file("Hello world");
+        my_logger.flush();
+        my_logger.log_to_file("Last words");
+        assert!(std::fs::read_to_string(&file)
+            .unwrap()
+            .ends_with("]: Hello world"));
+
+        drop(my_logger);
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        assert!(content.ends_with("]: Last words"));
+    }
+
+    #[test]
+    fn buffer_size_bounds_unflushed_lines() {
+        use crate::{FlushPolicy, Logger};
+
+        let file = std::env::temp_dir().join(format!("lawg-buffer-{}.txt", std::process::id()));
+        let mut my_logger = Logger::new(
+            "My Logger".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            true,
+        );
+        my_logger.set_format("{message}").unwrap();
+        my_logger.flush_policy = FlushPolicy::Manual;
+        my_logger.set_buffer_size(16);
+
+        // Each entry is 9 bytes, so the second one doesn't fit in the buffer with the first.
+        my_logger.log_to_file("12345678");
+        assert_eq!(std::fs::metadata(&file).unwrap().len(), 0);
+        my_logger.log_to_file("12345678");
+        assert_eq!(std::fs::metadata(&file).unwrap().len(), 9);
+
+        drop(my_logger);
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn children_share_the_file_with_prefixed_names() {
+        use crate::Logger;
+
+        let file = std::env::temp_dir().join(format!("lawg-children-{}.txt", std::process::id()));
+        let app = Logger::new(
+            "app".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            true,
+        );
+        let db = app.child("db");
+        let pool = db.child("pool");
+
+        app.log_to_file("Starting");
+        db.log_to_file("Connecting");
+        pool.log_to_file("Connection pool ready");
+        app.log_to_file("Started");
+
+        // Dropping a child leaves the shared file open for the others.
+        drop(pool);
+        db.error_to_file("Connection lost");
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        let lines: Vec<(&str, &str)> = content
+            .lines()
+            .skip(1)
+            .map(|line| {
+                let (name, rest) = line.split_once(" - [").unwrap();
+                (name, rest.split_once("]: ").unwrap().1)
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                ("app", "Starting"),
+                ("app::db", "Connecting"),
+                ("app::db::pool", "Connection pool ready"),
+                ("app", "Started"),
+                ("ERROR: app::db", "Connection lost"),
+            ]
+        );
+    }
+
+    #[test]
+    fn children_keep_their_own_settings() {
+        use crate::{Level, Logger};
+
+        let file =
+            std::env::temp_dir().join(format!("lawg-children-settings-{}.txt", std::process::id()));
+        let mut app = Logger::new(
+            "app".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            true,
+        );
+        app.set_format("{name}: {message}").unwrap();
+        app.set_min_level(Level::Info);
+
+        let db = app.child("db");
+        assert_eq!(db.min_level(), Level::Info);
+
+        // Settings changed on the parent later don't reach the child.
+        app.set_min_level(Level::Error);
+        app.set_format("{level} {name}: {message}").unwrap();
+
+        app.info("Dropped");
+        db.info("Kept");
+        app.error_to_file("Cereals are gone");
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        assert_eq!(content, "\napp::db: Kept\nERROR app: Cereals are gone");
+    }
+
+    #[test]
+    fn log_every_suppresses_within_the_interval() {
+        use crate::Logger;
+        use std::time::{Duration, Instant};
+
+        let file = std::env::temp_dir().join(format!("lawg-every-{}.txt", std::process::id()));
+        let (stdout, stderr) = (Captured::default(), Captured::default());
+        let mut logger = Logger::new(
+            "Test Logger".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            true,
+        );
+        logger.set_format("{message}").unwrap();
+        logger.set_console_writers(stdout.clone(), stderr.clone());
+
+        // Five windows of 100ms, each starting with a logged line.
+        let start = Instant::now();
+        let mut calls = 0;
+
+        while start.elapsed() < Duration::from_millis(450) {
+            logger.log_every("refused", Duration::from_millis(100), "Connection refused");
+            calls += 1;
+        }
+        // Once the window is over, the next line reports what the last one left out.
+        std::thread::sleep(Duration::from_millis(100));
+        logger.log_every("refused", Duration::from_millis(100), "Connection refused");
+        calls += 1;
+
+        logger.log_every("other", Duration::from_millis(100), "Other key");
+        logger.log_once("once", "First time");
+        logger.log_once("once", "Second time");
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        let lines: Vec<&str> = content.lines().skip(1).collect();
+        let (refused, others) = lines.split_at(lines.len() - 2);
+        assert_eq!(others, ["Other key", "First time"]);
+        // At most one line per 100ms of the loop, and the one after it.
+        assert!((2..=6).contains(&refused.len()), "{:?}", refused);
+        assert_eq!(refused[0], "Connection refused");
+
+        let counted: usize = refused
+            .iter()
+            .map(|line| {
+                let suppressed = match line.strip_prefix("Connection refused") {
+                    Some("") => "0",
+                    Some(rest) => rest
+                        .strip_prefix(" (suppressed ")
+                        .and_then(|rest| {
+                            rest.strip_suffix(" similar messages)")
+                                .or_else(|| rest.strip_suffix(" similar message)"))
+                        })
+                        .unwrap_or_else(|| panic!("unexpected line {:?}", line)),
+                    None => panic!("unexpected line {:?}", line),
+                };
+                1 + suppressed.parse::<usize>().unwrap()
+            })
+            .sum();
+        assert_eq!(counted, calls);
+
+        assert_eq!(stdout.take(), lines.join("\n") + "\n");
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn entries_round_trip() {
+        use crate::{Level, Logger};
+
+        let file = std::env::temp_dir().join(format!("lawg-entries-{}.txt", std::process::id()));
+        let start = chrono::Utc::now();
+        let logger = Logger::new(
+            "app".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            true,
+        );
+        let db = logger.child("db");
+        let local = Logger::new(
+            "local".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            false,
+        );
+        local.set_min_level(Level::Warn);
+
+        logger.log_to_file("Started");
+        db.error_to_file("Connection lost\n  retrying in 5s\n\nor not");
+        local.warn("Running low on cereals");
+        logger.log_with_fields("Request done", &[("status", "200")]);
+        local.flush();
+
+        let entries = logger.read_entries().unwrap();
+        let end = chrono::Utc::now();
+        std::fs::remove_file(&file).unwrap();
+
+        let read: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.level,
+                    entry.logger_name.as_str(),
+                    entry.message.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            read,
+            [
+                (Level::Info, "app", "Started"),
+                (
+                    Level::Error,
+                    "app::db",
+                    "Connection lost\n  retrying in 5s\n\nor not"
+                ),
+                (Level::Warn, "local", "Running low on cereals"),
+                (Level::Info, "app", "Request done status=200"),
+            ]
+        );
+        assert!(entries
+            .windows(2)
+            .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+        assert!(start <= entries[0].timestamp && entries[3].timestamp <= end);
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn tail_and_entries_since_pick_recent_entries() {
+        use crate::Logger;
+
+        let file = std::env::temp_dir().join(format!("lawg-tail-{}.txt", std::process::id()));
+        std::fs::write(
+            &file,
+            "\napp - [\"2021-11-08 10:59:32.5 UTC\"]: One\
+             \napp - [\"2021-11-08 10:59:33 UTC\"]: Two\
+             \napp - [\"garbage\"]: Three\
+             \napp - [\"2021-11-08 10:59:34 UTC\"]: Four",
+        )
+        .unwrap();
+        let stdout = Captured::default();
+        let mut logger = Logger::new(
+            "app".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            true,
+        );
+        logger.set_console_writers(stdout.clone(), Captured::default());
+
+        let messages = |entries: Vec<crate::LogEntry>| {
+            entries
+                .into_iter()
+                .map(|entry| entry.message)
+                .collect::<Vec<_>>()
+        };
+        let since = "2021-11-08T10:59:33Z".parse().unwrap();
+
+        assert_eq!(messages(logger.tail(2).unwrap()), ["Two", "Four"]);
+        assert_eq!(messages(logger.tail(9).unwrap()), ["One", "Two", "Four"]);
+        assert_eq!(
+            messages(logger.entries_since(since).unwrap()),
+            ["Two", "Four"]
+        );
+        std::fs::remove_file(&file).unwrap();
+
+        let warnings = stdout.take();
+        assert_eq!(warnings.lines().count(), 3);
+        assert!(warnings.contains("Skipping line 4 of "), "{}", warnings);
+        assert!(warnings.ends_with(": invalid timestamp\n"), "{}", warnings);
+
+        logger.set_format("{name}: {message}").unwrap();
+        assert!(matches!(
+            logger.read_entries(),
+            Err(crate::LoggerError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn async_logger_writes_every_line_before_dropping() {
+        use crate::{Logger, OverflowPolicy};
+
+        let file = std::env::temp_dir().join(format!("lawg-async-{}.txt", std::process::id()));
+        let mut logger = Logger::new(
+            "Test Logger".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            true,
+        );
+        logger.set_format("{message}").unwrap();
+        logger.set_async(1024, OverflowPolicy::Block);
+
+        for i in 0..100_000 {
+            logger.log_to_file(i);
+        }
+        drop(logger);
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        let lines: Vec<usize> = content
+            .lines()
+            .skip(1)
+            .map(|line| line.parse().unwrap())
+            .collect();
+        assert_eq!(lines, (0..100_000).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn drop_oldest_counts_the_lines_it_drops() {
+        use crate::{Logger, OverflowPolicy};
+
+        let file =
+            std::env::temp_dir().join(format!("lawg-drop-oldest-{}.txt", std::process::id()));
+        let mut logger = Logger::new(
+            "Test Logger".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            true,
+        );
+        logger.set_format("{message}").unwrap();
+        logger.set_async(1, OverflowPolicy::DropOldest);
+
+        for i in 0..10_000 {
+            logger.log_to_file(i);
+        }
+        logger.flush();
+        let dropped = logger.dropped_count();
+        logger.shutdown();
+        assert_eq!(logger.dropped_count(), 0);
+
+        // Back to writing on this thread.
+        logger.log_to_file("After shutdown");
+        drop(logger);
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        let mut lines: Vec<&str> = content.lines().skip(1).collect();
+        assert_eq!(lines.pop(), Some("After shutdown"));
+
+        let lines: Vec<usize> = lines.iter().map(|line| line.parse().unwrap()).collect();
+        assert_eq!(lines.len() as u64 + dropped, 10_000);
+        assert!(lines.windows(2).all(|pair| pair[0] < pair[1]));
+        // The newest line is never the one dropped.
+        assert_eq!(lines.last(), Some(&9_999));
+    }
+
+    /// Runs `error_and_stop_to_file` in a child process, since it exits.
+    /// Returns the child's stderr and the log file's content.
+    fn run_error_and_stop_to_file(test_name: &str, echo: bool) -> (String, String) {
+        use crate::Logger;
+        use std::process::Command;
+
+        let file =
+            std::env::temp_dir().join(format!("lawg-{}-{}.txt", test_name, std::process::id()));
+
+        if let Ok(file) = std::env::var("LAWG_FATAL_LOG") {
+            let mut my_logger = Logger::new("My Logger".to_string(), Some(file), true);
+            my_logger.echo_fatal_to_console = echo;
+            // Stopping must flush what's buffered.
+            my_logger.flush_policy = crate::FlushPolicy::Manual;
+            my_logger.error_and_stop_to_file("Out of cereals");
+        }
+
+        let output = Command::new(std::env::current_exe().unwrap())
+            .args([&format!("tests::{}", test_name), "--exact", "--nocapture"])
+            .env("LAWG_FATAL_LOG", &file)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        (String::from_utf8(output.stderr).unwrap(), content)
+    }
+
+    #[test]
+    fn error_and_stop_to_file_echoes_to_console() {
+        let (stderr, content) =
+            run_error_and_stop_to_file("error_and_stop_to_file_echoes_to_console", true);
+
+        assert!(content.contains("ERROR: My Logger - "));
+        assert!(content.ends_with("]: Out of cereals"));
+        assert!(stderr
+            .lines()
+            .any(|line| line.starts_with("ERROR: My Logger - ")
+                && line.ends_with("]: Out of cereals")));
+    }
+
+    #[test]
+    fn error_and_stop_to_file_echo_can_be_suppressed() {
+        let (stderr, content) =
+            run_error_and_stop_to_file("error_and_stop_to_file_echo_can_be_suppressed", false);
+
+        assert!(content.ends_with("]: Out of cereals"));
+        assert!(!stderr.contains("Out of cereals"));
+    }
+
+    #[derive(Default)]
+    struct RecordingCoordinator {
+        events: std::sync::Arc<std::sync::Mutex<Vec<&'static str>>>,
+        nested: std::sync::OnceLock<crate::Logger>,
+    }
+
+    /// A console writer recording its writes in the events of a `RecordingCoordinator`.
+    struct RecordingWriter(std::sync::Arc<std::sync::Mutex<Vec<&'static str>>>);
+
+    impl std::io::Write for RecordingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().push("write");
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl crate::ConsoleCoordinator for RecordingCoordinator {
+        fn before_write(&self) {
+            self.events.lock().unwrap().push("before");
+
+            if let Some(nested) = self.nested.get() {
+                nested.log("Logged from the coordinator");
+            }
+        }
+
+        fn after_write(&self) {
+            self.events.lock().unwrap().push("after");
+        }
+    }
+
+    #[test]
+    fn console_coordinator_runs_around_console_writes_only() {
+        use crate::Logger;
+        use std::sync::Arc;
+
+        let file =
+            std::env::temp_dir().join(format!("lawg-coordinator-{}.txt", std::process::id()));
+        let coordinator = Arc::new(RecordingCoordinator::default());
+
+        let mut my_logger = Logger::new(
+            "My Logger".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            true,
+        );
+        my_logger.set_console_coordinator(coordinator.clone());
+        my_logger.set_console_writers(
+            RecordingWriter(coordinator.events.clone()),
+            RecordingWriter(coordinator.events.clone()),
+        );
+
+        my_logger.log("Hello world");
+        my_logger.error("Cereals are gone");
+        assert_eq!(
+            *coordinator.events.lock().unwrap(),
+            ["before", "write", "after", "before", "write", "after"]
+        );
+
+        my_logger.log_to_file("Hello world 123");
+        my_logger.error_to_file("Cereals are gone");
+        assert_eq!(coordinator.events.lock().unwrap().len(), 6);
+
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn console_coordinator_can_log() {
+        use crate::Logger;
+        use std::sync::Arc;
+
+        let coordinator = Arc::new(RecordingCoordinator::default());
+
+        let mut nested = Logger::new("Nested Logger".to_string(), None, true);
+        nested.set_console_coordinator(coordinator.clone());
+        coordinator.nested.set(nested).unwrap();
+
+        let mut my_logger = Logger::new("My Logger".to_string(), None, true);
+        my_logger.set_console_coordinator(coordinator.clone());
+        my_logger.log("Hello world");
+
+        assert_eq!(*coordinator.events.lock().unwrap(), ["before", "after"]);
+    }
+
+    #[cfg(not(feature = "chrono"))]
+    #[test]
+    #[should_panic(expected = "Local time is unavailable")]
+    fn local_time_needs_chrono() {
+        assert!(matches!(
+            crate::Logger::try_new("My Logger".to_string(), None, false),
+            Err(crate::LoggerError::LocalTimeUnavailable)
+        ));
+
+        crate::Logger::new("My Logger".to_string(), None, false);
     }
 }
@@ -0,0 +1,171 @@
+//! Rotation of log files, see `Logger.rotation`.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// When a `Logger` moves its log file aside and starts a fresh one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotationPolicy {
+    /// Before an entry would grow the file past this many bytes.
+    /// The current file becomes `logs.txt.1`, and older ones shift to `logs.txt.2` and so on.
+    MaxSize(u64),
+    /// Before the first entry of a new day, in local time unless `Logger.use_utc` is set.
+    /// The current file becomes `logs.txt.yyyy-mm-dd`, after the day it was written on.
+    Daily,
+}
+
+/// Moves `path` aside to make room for a fresh file,
+/// keeping at most `max_backups` rotated files if it's set.
+/// `date` is the day the file was written on, for `RotationPolicy::Daily`.
+pub(crate) fn rotate(
+    path: &str,
+    policy: RotationPolicy,
+    max_backups: Option<usize>,
+    date: (i64, u32, u32),
+) -> io::Result<()> {
+    if max_backups == Some(0) {
+        return fs::remove_file(path);
+    }
+
+    match policy {
+        RotationPolicy::MaxSize(_) => {
+            let numbered = |i: usize| PathBuf::from(format!("{}.{}", path, i));
+            let last = max_backups.unwrap_or_else(|| {
+                (1..)
+                    .find(|&i| !numbered(i).exists())
+                    .expect("ran out of backup numbers")
+            });
+
+            for i in (1..last).rev() {
+                if numbered(i).exists() {
+                    fs::rename(numbered(i), numbered(i + 1))?;
+                }
+            }
+
+            fs::rename(path, numbered(1))
+        }
+        RotationPolicy::Daily => {
+            let (year, month, day) = date;
+            let dated = format!("{}.{:04}-{:02}-{:02}", path, year, month, day);
+
+            // A file dated that day is only there if it was rotated before, e.g. by another run.
+            let target = std::iter::once(PathBuf::from(&dated))
+                .chain((1..).map(|i| PathBuf::from(format!("{}.{}", dated, i))))
+                .find(|target| !target.exists())
+                .expect("ran out of backup numbers");
+            fs::rename(path, target)?;
+
+            match max_backups {
+                Some(max_backups) => remove_oldest_dated(path, max_backups),
+                None => Ok(()),
+            }
+        }
+    }
+}
+
+/// Removes the oldest `logs.txt.yyyy-mm-dd` files next to `path`, keeping `keep` of them.
+fn remove_oldest_dated(path: &str, keep: usize) -> io::Result<()> {
+    let path = Path::new(path);
+    let prefix = match path.file_name().and_then(|name| name.to_str()) {
+        Some(name) => format!("{}.", name),
+        None => return Ok(()),
+    };
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    let mut dated = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let name = entry?.file_name();
+
+        if let Some(suffix) = name.to_str().and_then(|name| name.strip_prefix(&prefix)) {
+            if let Some((date, number)) = parse_dated(suffix) {
+                dated.push(((date.to_string(), number), suffix.to_string()));
+            }
+        }
+    }
+
+    // Dates sort by their text, and same-day files by their number, so `.10` comes after `.2`.
+    dated.sort();
+
+    for (_, suffix) in &dated[..dated.len().saturating_sub(keep)] {
+        fs::remove_file(dir.join(format!("{}{}", prefix, suffix)))?;
+    }
+
+    Ok(())
+}
+
+/// Splits a `yyyy-mm-dd` date, optionally followed by a `.n` number, into the date and the number,
+/// which is `0` without one.
+fn parse_dated(name: &str) -> Option<(&str, u64)> {
+    let (date, number) = name.split_at_checked(10)?;
+
+    let is_date = date.char_indices().all(|(i, ch)| match i {
+        4 | 7 => ch == '-',
+        _ => ch.is_ascii_digit(),
+    });
+    if !is_date {
+        return None;
+    }
+
+    match number.strip_prefix('.') {
+        None if number.is_empty() => Some((date, 0)),
+        Some(n) if !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()) => {
+            Some((date, n.parse().ok()?))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_dated, remove_oldest_dated};
+
+    #[test]
+    fn parses_dated_names() {
+        assert_eq!(parse_dated("2021-11-08"), Some(("2021-11-08", 0)));
+        assert_eq!(parse_dated("2021-11-08.12"), Some(("2021-11-08", 12)));
+        assert_eq!(parse_dated("2021-11-08."), None);
+        assert_eq!(parse_dated("2021-11-08.1a"), None);
+        assert_eq!(parse_dated("2021-11-8"), None);
+        assert_eq!(parse_dated("1"), None);
+    }
+
+    #[test]
+    fn same_day_backups_are_pruned_by_number() {
+        let dir = std::env::temp_dir().join(format!("lawg-prune-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("logs.txt");
+        let path = path.to_str().unwrap();
+
+        let mut names = vec![
+            format!("{}.2021-11-07", path),
+            format!("{}.2021-11-08", path),
+        ];
+        names.extend((1..=11).map(|i| format!("{}.2021-11-08.{}", path, i)));
+        for name in &names {
+            std::fs::write(name, "").unwrap();
+        }
+
+        remove_oldest_dated(path, 3).unwrap();
+
+        let mut kept: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        kept.sort();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            kept,
+            [
+                "logs.txt.2021-11-08.10",
+                "logs.txt.2021-11-08.11",
+                "logs.txt.2021-11-08.9"
+            ]
+        );
+    }
+}
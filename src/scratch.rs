@@ -0,0 +1,76 @@
+//! A reusable per-thread buffer for rendering log lines.
+
+use std::cell::RefCell;
+
+/// Buffers that grew past this many bytes are freed after use, so one huge message
+/// doesn't keep its memory around for the rest of the thread's life.
+const SHRINK_THRESHOLD: usize = 4096;
+
+thread_local! {
+    static SCRATCH: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// Runs `f` with this thread's scratch buffer, cleared.
+///
+/// If the buffer is already in use, because something inside `f` logged on the same thread,
+/// or is gone because the thread is shutting down, `f` gets a fresh `String` instead.
+pub(crate) fn with_scratch<R>(f: impl FnOnce(&mut String) -> R) -> R {
+    let mut f = Some(f);
+
+    let reused = SCRATCH.try_with(|scratch| {
+        let mut scratch = scratch.try_borrow_mut().ok()?;
+        scratch.clear();
+
+        let result = (f.take().unwrap())(&mut scratch);
+
+        if scratch.capacity() > SHRINK_THRESHOLD {
+            *scratch = String::new();
+        }
+
+        Some(result)
+    });
+
+    match reused {
+        Ok(Some(result)) => result,
+        _ => (f.take().unwrap())(&mut String::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{with_scratch, SHRINK_THRESHOLD};
+
+    #[test]
+    fn reuses_the_buffer() {
+        let first = with_scratch(|scratch| {
+            scratch.push_str("first");
+            scratch.as_ptr()
+        });
+        let second = with_scratch(|scratch| {
+            assert!(scratch.is_empty());
+            scratch.as_ptr()
+        });
+
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn nested_use_gets_a_fresh_buffer() {
+        with_scratch(|outer| {
+            outer.push_str("outer");
+
+            with_scratch(|inner| {
+                assert!(inner.is_empty());
+                inner.push_str("inner");
+            });
+
+            assert_eq!(outer, "outer");
+        });
+    }
+
+    #[test]
+    fn frees_large_buffers() {
+        with_scratch(|scratch| scratch.push_str(&"x".repeat(SHRINK_THRESHOLD + 1)));
+        with_scratch(|scratch| assert!(scratch.capacity() <= SHRINK_THRESHOLD));
+    }
+}
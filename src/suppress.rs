@@ -0,0 +1,106 @@
+//! Deduplication of repeated messages, see `Logger::log_once` and `Logger::log_every`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// What a `Logger` remembers about the keys it was given.
+#[derive(Debug, Default)]
+pub(crate) struct Suppressor {
+    keys: Mutex<HashMap<String, Key>>,
+}
+
+#[derive(Debug)]
+struct Key {
+    /// When a message with this key was last logged.
+    logged: Instant,
+    /// Messages with this key not logged since then.
+    suppressed: u64,
+}
+
+impl Suppressor {
+    /// Whether a message with `key` may be logged now: once per `interval`, or only the first time without one.
+    /// If it may, returns how many were suppressed since the last one logged.
+    pub(crate) fn allow(&self, key: &str, interval: Option<Duration>) -> Option<u64> {
+        let mut keys = self.keys.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = Instant::now();
+
+        // Only a key seen for the first time is copied.
+        let Some(seen) = keys.get_mut(key) else {
+            keys.insert(
+                key.to_string(),
+                Key {
+                    logged: now,
+                    suppressed: 0,
+                },
+            );
+            return Some(0);
+        };
+
+        match interval {
+            Some(interval) if now.duration_since(seen.logged) >= interval => {
+                seen.logged = now;
+                Some(std::mem::take(&mut seen.suppressed))
+            }
+            _ => {
+                seen.suppressed += 1;
+                None
+            }
+        }
+    }
+}
+
+/// A message followed by ` (suppressed n similar messages)` if any were.
+pub(crate) struct WithSuppressed<T> {
+    pub(crate) msg: T,
+    pub(crate) suppressed: u64,
+}
+
+impl<T: fmt::Display> fmt::Display for WithSuppressed<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.suppressed {
+            0 => write!(f, "{}", self.msg),
+            1 => write!(f, "{} (suppressed 1 similar message)", self.msg),
+            n => write!(f, "{} (suppressed {} similar messages)", self.msg, n),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Suppressor, WithSuppressed};
+    use std::time::Duration;
+
+    #[test]
+    fn keys_are_suppressed_separately() {
+        let suppressor = Suppressor::default();
+
+        assert_eq!(suppressor.allow("a", None), Some(0));
+        assert_eq!(suppressor.allow("a", None), None);
+        assert_eq!(suppressor.allow("b", None), Some(0));
+
+        assert_eq!(suppressor.allow("c", Some(Duration::ZERO)), Some(0));
+        assert_eq!(suppressor.allow("c", Some(Duration::ZERO)), Some(0));
+        assert_eq!(
+            suppressor.allow("d", Some(Duration::from_secs(60))),
+            Some(0)
+        );
+        assert_eq!(suppressor.allow("d", Some(Duration::from_secs(60))), None);
+    }
+
+    #[test]
+    fn suffix_counts_suppressed_messages() {
+        let line = |suppressed| {
+            WithSuppressed {
+                msg: "Refused",
+                suppressed,
+            }
+            .to_string()
+        };
+
+        assert_eq!(line(0), "Refused");
+        assert_eq!(line(1), "Refused (suppressed 1 similar message)");
+        assert_eq!(line(314), "Refused (suppressed 314 similar messages)");
+    }
+}
@@ -0,0 +1,499 @@
+//! Timestamps for log lines.
+//!
+//! Without the `chrono` feature, timestamps come from a small std-only UTC renderer, [`UtcTime`].
+//! Its output mirrors chrono's, so switching the feature doesn't change the log lines:
+//! `Display` renders like `DateTime<Utc>::to_string()` and [`UtcTime::to_rfc3339`]
+//! like `DateTime<Utc>::to_rfc3339()`. [`write_strftime`] implements the common subset of
+//! chrono's strftime specifiers, for `Logger::set_time_format`.
+
+#![cfg_attr(feature = "chrono", allow(dead_code))]
+
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The time a log line was written, rendered without allocating.
+pub(crate) enum Timestamp {
+    #[cfg(feature = "chrono")]
+    Utc(chrono::DateTime<chrono::Utc>),
+    #[cfg(feature = "chrono")]
+    Local(chrono::DateTime<chrono::Local>),
+    #[cfg(not(feature = "chrono"))]
+    Std(UtcTime),
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "chrono")]
+            Timestamp::Utc(time) => time.fmt(f),
+            #[cfg(feature = "chrono")]
+            Timestamp::Local(time) => time.fmt(f),
+            #[cfg(not(feature = "chrono"))]
+            Timestamp::Std(time) => time.fmt(f),
+        }
+    }
+}
+
+impl Timestamp {
+    /// Renders with the strftime-style `format`, or like `Display` without one.
+    /// The format must have been accepted by `check_format`.
+    pub(crate) fn with_format<'a>(&'a self, format: Option<&'a str>) -> impl fmt::Display + 'a {
+        Formatted(self, format)
+    }
+
+    /// Renders as RFC 3339, like `2021-11-08T10:59:32.007546200+00:00`.
+    pub(crate) fn rfc3339(&self) -> impl fmt::Display + '_ {
+        Formatted(self, Some("%+"))
+    }
+}
+
+struct Formatted<'a>(&'a Timestamp, Option<&'a str>);
+
+impl fmt::Display for Formatted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(format) = self.1 else {
+            return self.0.fmt(f);
+        };
+
+        match self.0 {
+            #[cfg(feature = "chrono")]
+            Timestamp::Utc(time) => time.format(format).fmt(f),
+            #[cfg(feature = "chrono")]
+            Timestamp::Local(time) => time.format(format).fmt(f),
+            #[cfg(not(feature = "chrono"))]
+            Timestamp::Std(time) => write_strftime(time, format, f),
+        }
+    }
+}
+
+/// Checks that every specifier of the strftime-style `format` is supported.
+pub(crate) fn check_format(format: &str) -> Result<(), String> {
+    #[cfg(feature = "chrono")]
+    let unsupported = chrono::format::StrftimeItems::new(format)
+        .any(|item| matches!(item, chrono::format::Item::Error));
+
+    #[cfg(not(feature = "chrono"))]
+    let unsupported = specifiers(format).any(|spec| {
+        spec.starts_with('%')
+            && write_specifier(&UtcTime::from_unix(0, 0), spec, &mut String::new()).is_none()
+    });
+
+    match unsupported {
+        true => Err(format!("unsupported time format `{}`", format)),
+        false => Ok(()),
+    }
+}
+
+/// The `(year, month, day)` that `time` falls on, in local time unless `use_utc` is set.
+pub(crate) fn date(time: SystemTime, use_utc: bool) -> (i64, u32, u32) {
+    #[cfg(feature = "chrono")]
+    if !use_utc {
+        use chrono::Datelike;
+
+        let local = chrono::DateTime::<chrono::Local>::from(time);
+        return (i64::from(local.year()), local.month(), local.day());
+    }
+
+    // Local time needs chrono, so without it a `Logger` always uses UTC.
+    #[cfg(not(feature = "chrono"))]
+    let _ = use_utc;
+
+    let time = UtcTime::from_system_time(time);
+    (time.year, time.month, time.day)
+}
+
+const SECS_PER_DAY: i64 = 86_400;
+
+/// A point in time broken down into its UTC calendar fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct UtcTime {
+    secs: i64,
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+    nanos: u32,
+}
+
+impl UtcTime {
+    /// The current time.
+    pub(crate) fn now() -> Self {
+        Self::from_system_time(SystemTime::now())
+    }
+
+    /// Converts a `SystemTime`, including ones before the Unix epoch.
+    pub(crate) fn from_system_time(time: SystemTime) -> Self {
+        match time.duration_since(UNIX_EPOCH) {
+            Ok(since) => Self::from_unix(since.as_secs() as i64, since.subsec_nanos()),
+            Err(err) => {
+                let before = err.duration();
+                let secs = -(before.as_secs() as i64);
+
+                match before.subsec_nanos() {
+                    0 => Self::from_unix(secs, 0),
+                    nanos => Self::from_unix(secs - 1, 1_000_000_000 - nanos),
+                }
+            }
+        }
+    }
+
+    /// Converts seconds (and nanoseconds past that second) since the Unix epoch.
+    pub(crate) fn from_unix(secs: i64, nanos: u32) -> Self {
+        let (year, month, day) = civil_from_days(secs.div_euclid(SECS_PER_DAY));
+        let secs_of_day = secs.rem_euclid(SECS_PER_DAY) as u32;
+
+        UtcTime {
+            secs,
+            year,
+            month,
+            day,
+            hour: secs_of_day / 3600,
+            minute: secs_of_day / 60 % 60,
+            second: secs_of_day % 60,
+            nanos,
+        }
+    }
+
+    /// Renders the time as RFC 3339, e.g. `2021-11-08T10:59:32.007546200+00:00`.
+    #[allow(dead_code)]
+    pub(crate) fn to_rfc3339(self) -> String {
+        Rfc3339(&self).to_string()
+    }
+}
+
+/// Renders a `UtcTime` as RFC 3339, without allocating.
+struct Rfc3339<'a>(&'a UtcTime);
+
+impl fmt::Display for Rfc3339<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let time = self.0;
+
+        write!(
+            f,
+            "{}-{:02}-{:02}T{:02}:{:02}:{:02}{}+00:00",
+            Year(time.year),
+            time.month,
+            time.day,
+            time.hour,
+            time.minute,
+            time.second,
+            Fraction(time.nanos)
+        )
+    }
+}
+
+/// Renders like chrono, e.g. `2021-11-08 10:59:32.007546200 UTC`.
+impl fmt::Display for UtcTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{:02}-{:02} {:02}:{:02}:{:02}{} UTC",
+            Year(self.year),
+            self.month,
+            self.day,
+            self.hour,
+            self.minute,
+            self.second,
+            Fraction(self.nanos)
+        )
+    }
+}
+
+/// Writes `time` formatted with the strftime-style `format`, which must be accepted by `check_format`.
+///
+/// Supported are `%Y %y %m %b %h %B %d %e %a %A %H %I %p %M %S %f %.f %.3f %.6f %.9f %3f %6f %9f
+/// %s %z %:z %Z %+ %F %T %R %D %% %n %t`, which render like chrono's.
+pub(crate) fn write_strftime(
+    time: &UtcTime,
+    format: &str,
+    out: &mut dyn fmt::Write,
+) -> fmt::Result {
+    for spec in specifiers(format) {
+        if spec.starts_with('%') {
+            write_specifier(time, spec, out).unwrap_or(Err(fmt::Error))?;
+        } else {
+            out.write_str(spec)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Splits a strftime-style format into literal text and specifiers like `%H`, `%.3f` or `%:z`.
+fn specifiers(format: &str) -> impl Iterator<Item = &str> {
+    let mut rest = format;
+
+    std::iter::from_fn(move || {
+        let len = match rest.find('%') {
+            Some(0) => {
+                let flags = rest[1..]
+                    .find(|ch: char| !matches!(ch, '.' | ':' | '0'..='9'))
+                    .unwrap_or(rest.len() - 1);
+                let spec_len = rest[1 + flags..].chars().next().map_or(0, char::len_utf8);
+
+                1 + flags + spec_len
+            }
+            Some(len) => len,
+            None if rest.is_empty() => return None,
+            None => rest.len(),
+        };
+
+        let (piece, remaining) = rest.split_at(len);
+        rest = remaining;
+        Some(piece)
+    })
+}
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+/// Writes one specifier of `write_strftime`, or returns `None` if it isn't supported.
+fn write_specifier(time: &UtcTime, spec: &str, out: &mut dyn fmt::Write) -> Option<fmt::Result> {
+    let month = MONTHS[time.month as usize - 1];
+    // 1970-01-01 was a Thursday.
+    let weekday = WEEKDAYS[(time.secs.div_euclid(SECS_PER_DAY) + 4).rem_euclid(7) as usize];
+
+    Some(match spec {
+        "%Y" => write!(out, "{}", Year(time.year)),
+        "%y" => write!(out, "{:02}", time.year.rem_euclid(100)),
+        "%m" => write!(out, "{:02}", time.month),
+        "%b" | "%h" => out.write_str(&month[..3]),
+        "%B" => out.write_str(month),
+        "%d" => write!(out, "{:02}", time.day),
+        "%e" => write!(out, "{:2}", time.day),
+        "%a" => out.write_str(&weekday[..3]),
+        "%A" => out.write_str(weekday),
+        "%H" => write!(out, "{:02}", time.hour),
+        "%I" => write!(out, "{:02}", (time.hour + 11) % 12 + 1),
+        "%p" => out.write_str(if time.hour < 12 { "AM" } else { "PM" }),
+        "%M" => write!(out, "{:02}", time.minute),
+        "%S" => write!(out, "{:02}", time.second),
+        "%f" => write!(out, "{:09}", time.nanos),
+        "%.f" => write!(out, "{}", Fraction(time.nanos)),
+        "%.3f" => write!(out, ".{:03}", time.nanos / 1_000_000),
+        "%.6f" => write!(out, ".{:06}", time.nanos / 1_000),
+        "%.9f" => write!(out, ".{:09}", time.nanos),
+        "%3f" => write!(out, "{:03}", time.nanos / 1_000_000),
+        "%6f" => write!(out, "{:06}", time.nanos / 1_000),
+        "%9f" => write!(out, "{:09}", time.nanos),
+        "%s" => write!(out, "{}", time.secs),
+        "%z" => out.write_str("+0000"),
+        "%:z" => out.write_str("+00:00"),
+        "%Z" => out.write_str("UTC"),
+        "%+" => write!(out, "{}", Rfc3339(time)),
+        "%F" => write!(out, "{}-{:02}-{:02}", Year(time.year), time.month, time.day),
+        "%T" => write!(
+            out,
+            "{:02}:{:02}:{:02}",
+            time.hour, time.minute, time.second
+        ),
+        "%R" => write!(out, "{:02}:{:02}", time.hour, time.minute),
+        "%D" => write!(
+            out,
+            "{:02}/{:02}/{:02}",
+            time.month,
+            time.day,
+            time.year.rem_euclid(100)
+        ),
+        "%%" => out.write_str("%"),
+        "%n" => out.write_str("\n"),
+        "%t" => out.write_str("\t"),
+        _ => return None,
+    })
+}
+
+/// Four digits for years 0 to 9999, otherwise signed, as chrono does.
+struct Year(i64);
+
+impl fmt::Display for Year {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if (0..=9999).contains(&self.0) {
+            write!(f, "{:04}", self.0)
+        } else {
+            write!(f, "{:+05}", self.0)
+        }
+    }
+}
+
+/// Fractional seconds using the fewest of 0, 3, 6 or 9 digits that are exact, as chrono does.
+struct Fraction(u32);
+
+impl fmt::Display for Fraction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let nanos = self.0;
+
+        if nanos == 0 {
+            Ok(())
+        } else if nanos.is_multiple_of(1_000_000) {
+            write!(f, ".{:03}", nanos / 1_000_000)
+        } else if nanos.is_multiple_of(1_000) {
+            write!(f, ".{:06}", nanos / 1_000)
+        } else {
+            write!(f, ".{:09}", nanos)
+        }
+    }
+}
+
+/// Converts days since 1970-01-01 into a proleptic Gregorian `(year, month, day)`.
+///
+/// This is the `civil_from_days` algorithm from
+/// <http://howardhinnant.github.io/date_algorithms.html>.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_strftime, UtcTime};
+
+    fn strftime(time: UtcTime, format: &str) -> String {
+        let mut out = String::new();
+        write_strftime(&time, format, &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn renders_known_instants() {
+        assert_eq!(
+            UtcTime::from_unix(0, 0).to_string(),
+            "1970-01-01 00:00:00 UTC"
+        );
+        assert_eq!(
+            UtcTime::from_unix(1_636_369_172, 7_546_200).to_string(),
+            "2021-11-08 10:59:32.007546200 UTC"
+        );
+        assert_eq!(
+            UtcTime::from_unix(951_782_400, 250_000_000).to_rfc3339(),
+            "2000-02-29T00:00:00.250+00:00"
+        );
+        assert_eq!(
+            UtcTime::from_unix(-1, 0).to_string(),
+            "1969-12-31 23:59:59 UTC"
+        );
+    }
+
+    #[test]
+    fn renders_strftime_formats() {
+        let time = UtcTime::from_unix(1_636_369_172, 7_546_200);
+
+        assert_eq!(
+            strftime(time, "%Y-%m-%d %H:%M:%S%.3f"),
+            "2021-11-08 10:59:32.007"
+        );
+        assert_eq!(
+            strftime(time, "%a %e %b %I:%M %p, 100%%"),
+            "Mon  8 Nov 10:59 AM, 100%"
+        );
+        assert_eq!(strftime(time, "%s%.f%:z"), "1636369172.007546200+00:00");
+    }
+
+    #[test]
+    fn handles_times_before_the_epoch() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let time = UNIX_EPOCH - Duration::new(1, 500_000_000);
+        assert_eq!(
+            UtcTime::from_system_time(time),
+            UtcTime::from_unix(-2, 500_000_000)
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn matches_chrono() {
+        use chrono::{TimeZone, Utc};
+
+        // Years 0000 to 9999, the range where both renderers use plain four-digit years.
+        const MIN: i64 = -62_167_219_200;
+        const MAX: i64 = 253_402_300_799;
+
+        // A fixed xorshift sequence keeps failures reproducible.
+        let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for i in 0..100_000 {
+            let secs = MIN + (next() % (MAX - MIN) as u64) as i64;
+            let nanos = match i % 4 {
+                0 => 0,
+                1 => (next() % 1000) as u32 * 1_000_000,
+                2 => (next() % 1_000_000) as u32 * 1000,
+                _ => (next() % 1_000_000_000) as u32,
+            };
+
+            let ours = UtcTime::from_unix(secs, nanos);
+            let theirs = Utc.timestamp_opt(secs, nanos).unwrap();
+
+            assert_eq!(ours.to_string(), theirs.to_string());
+            assert_eq!(ours.to_rfc3339(), theirs.to_rfc3339());
+        }
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn strftime_matches_chrono() {
+        use chrono::{TimeZone, Utc};
+
+        const FORMAT: &str = "%Y %y %m %b %h %B %d %e %a %A %H %I %p %M %S %f %.f %.3f %.6f %.9f \
+                              %3f %6f %9f %s %z %:z %Z %+ %F %T %R %D %% %n %t";
+
+        let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for _ in 0..10_000 {
+            let secs = (next() % 253_402_300_799) as i64;
+            let nanos = (next() % 1_000_000_000) as u32;
+
+            assert_eq!(
+                strftime(UtcTime::from_unix(secs, nanos), FORMAT),
+                Utc.timestamp_opt(secs, nanos)
+                    .unwrap()
+                    .format(FORMAT)
+                    .to_string()
+            );
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Word-wrapping for console lines.
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Wraps `msg` so that `header` followed by the message fits in `width` columns.
+///
+/// The header is never wrapped. Continuation lines are indented to the column the
+/// message starts at, and widths are measured in terminal columns, not bytes.
+/// ANSI color codes in the header take no columns.
+/// Lines of the message that already fit are kept as they are, otherwise they are
+/// broken at spaces, and words wider than the available space are broken between characters.
+pub(crate) fn wrap(header: &str, msg: &str, width: usize) -> String {
+    let indent = width_without_colors(header);
+    let available = width.saturating_sub(indent).max(1);
+
+    let mut wrapped = String::from(header);
+    let mut column = 0;
+
+    let new_line = |wrapped: &mut String, column: &mut usize| {
+        wrapped.push('\n');
+        wrapped.extend(std::iter::repeat_n(' ', indent));
+        *column = 0;
+    };
+
+    for (i, line) in msg.split('\n').enumerate() {
+        if i > 0 {
+            new_line(&mut wrapped, &mut column);
+        }
+
+        if line.width() <= available {
+            wrapped.push_str(line);
+            continue;
+        }
+
+        for word in line.split(' ').filter(|word| !word.is_empty()) {
+            let word_width = word.width();
+
+            if column > 0 && column + 1 + word_width > available {
+                new_line(&mut wrapped, &mut column);
+            } else if column > 0 {
+                wrapped.push(' ');
+                column += 1;
+            }
+
+            if word_width <= available {
+                wrapped.push_str(word);
+                column += word_width;
+                continue;
+            }
+
+            for ch in word.chars() {
+                let ch_width = ch.width().unwrap_or(0);
+
+                if column > 0 && column + ch_width > available {
+                    new_line(&mut wrapped, &mut column);
+                }
+
+                wrapped.push(ch);
+                column += ch_width;
+            }
+        }
+    }
+
+    wrapped
+}
+
+/// The width of `text` in terminal columns, leaving out ANSI escape sequences like `\x1b[31m`.
+fn width_without_colors(text: &str) -> usize {
+    let mut width = 0;
+    let mut rest = text;
+
+    while let Some(start) = rest.find('\x1b') {
+        width += rest[..start].width();
+
+        // A sequence ends with its first character from `@` to `~`, after the `[`.
+        let sequence = &rest[start + 1..];
+        let end = sequence
+            .char_indices()
+            .skip(1)
+            .find(|&(_, ch)| ('@'..='~').contains(&ch))
+            .map_or(sequence.len(), |(i, ch)| i + ch.len_utf8());
+        rest = &sequence[end..];
+    }
+
+    width + rest.width()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::wrap;
+
+    #[test]
+    fn short_messages_are_untouched() {
+        assert_eq!(wrap("app: ", "hello  world", 40), "app: hello  world");
+    }
+
+    #[test]
+    fn wraps_ascii_at_word_boundaries() {
+        assert_eq!(
+            wrap("app: ", "the quick brown fox jumps over the lazy dog", 20),
+            "app: the quick brown\n     fox jumps over\n     the lazy dog"
+        );
+    }
+
+    #[test]
+    fn breaks_words_wider_than_the_line() {
+        assert_eq!(
+            wrap("app: ", "see abcdefghijklmnopqrstuvwxyz", 15),
+            "app: see\n     abcdefghij\n     klmnopqrst\n     uvwxyz"
+        );
+    }
+
+    #[test]
+    fn keeps_existing_line_breaks_indented() {
+        assert_eq!(wrap("app: ", "one\ntwo", 20), "app: one\n     two");
+    }
+
+    #[test]
+    fn colors_in_the_header_take_no_columns() {
+        assert_eq!(
+            wrap("\x1b[31mERROR:\x1b[0m ", "one two three", 14),
+            "\x1b[31mERROR:\x1b[0m one two\n       three"
+        );
+    }
+
+    #[test]
+    fn measures_cjk_by_display_width() {
+        // Each of these characters is two columns wide, so five fit in ten columns.
+        assert_eq!(
+            wrap("app: ", "日本語のテキストです", 15),
+            "app: 日本語のテ\n     キストです"
+        );
+        assert_eq!(
+            wrap("名前: ", "一 二三 四五六", 13),
+            "名前: 一 二三\n      四五六"
+        );
+    }
+}
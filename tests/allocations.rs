@@ -0,0 +1,123 @@
+//! Checks that logging doesn't allocate once warmed up.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::process::{Command, Stdio};
+
+use lawg::Logger;
+
+struct CountingAllocator;
+
+thread_local! {
+    /// This thread's allocations while it's counting them, so other threads' allocations,
+    /// like the test harness's, aren't counted. `const`, so using it never allocates.
+    static ALLOCATIONS: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// Counts an allocation, if this thread is counting them.
+fn count() {
+    // Threads being torn down have no thread-locals anymore, and aren't counting.
+    ALLOCATIONS
+        .try_with(|allocations| allocations.set(allocations.get().map(|n| n + 1)))
+        .ok();
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count();
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count();
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Logs 2000 short lines with `log_line` after a warm-up round and returns how many allocations
+/// this thread made for them.
+///
+/// The test harness captures stdout into a growing buffer, which would be counted too,
+/// so this runs `test_name` again in a child process with `--nocapture` and its stdout discarded.
+/// Returns `None` in the parent, after checking that the child succeeded.
+fn count_allocations(test_name: &str, log_line: impl Fn(usize)) -> Option<usize> {
+    if std::env::var_os("LAWG_COUNT_ALLOCATIONS").is_none() {
+        let output = Command::new(std::env::current_exe().unwrap())
+            .args([test_name, "--exact", "--nocapture"])
+            .env("LAWG_COUNT_ALLOCATIONS", "1")
+            .stdout(Stdio::null())
+            .output()
+            .unwrap();
+
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        return None;
+    }
+
+    // The first round grows the buffers to the longest line.
+    (0..2000).for_each(&log_line);
+
+    ALLOCATIONS.set(Some(0));
+    (0..2000).for_each(&log_line);
+
+    ALLOCATIONS.take()
+}
+
+#[test]
+fn console_logging_does_not_allocate() {
+    let my_logger = Logger::new("My Logger".to_string(), None, true);
+
+    let allocations = count_allocations("console_logging_does_not_allocate", |i| {
+        if i % 2 == 0 {
+            my_logger.log(format_args!("Request {} done", i));
+        } else {
+            my_logger.error("Cereals are gone");
+        }
+    });
+
+    if let Some(allocations) = allocations {
+        assert_eq!(
+            allocations, 0,
+            "logging 2000 lines allocated {} times",
+            allocations
+        );
+    }
+}
+
+#[test]
+fn file_logging_does_not_allocate() {
+    let file = std::env::temp_dir().join(format!("lawg-allocations-{}.txt", std::process::id()));
+    let my_logger = Logger::new(
+        "My Logger".to_string(),
+        Some(file.to_str().unwrap().to_string()),
+        true,
+    );
+
+    let allocations = count_allocations("file_logging_does_not_allocate", |i| {
+        if i % 2 == 0 {
+            my_logger.log_to_file(format_args!("Request {} done", i));
+        } else {
+            my_logger.error_to_file("Cereals are gone");
+        }
+    });
+
+    std::fs::remove_file(&file).unwrap();
+
+    if let Some(allocations) = allocations {
+        assert_eq!(
+            allocations, 0,
+            "logging 2000 lines allocated {} times",
+            allocations
+        );
+    }
+}
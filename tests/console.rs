@@ -0,0 +1,69 @@
+//! Checks console output as a whole, by running the logging in a child process and reading its stdout.
+
+use std::process::Command;
+use std::sync::Arc;
+
+use lawg::Logger;
+
+/// Runs `test_name` again in a child process with `LAWG_CHILD` set and returns its stdout.
+fn child_stdout(test_name: &str) -> String {
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args([test_name, "--exact", "--nocapture", "--quiet"])
+        .env("LAWG_CHILD", "1")
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn concurrent_lines_are_not_torn() {
+    const THREADS: usize = 8;
+    const LINES: usize = 500;
+
+    if std::env::var_os("LAWG_CHILD").is_some() {
+        let my_logger = Arc::new(Logger::new("My Logger".to_string(), None, true));
+
+        let threads: Vec<_> = (0..THREADS)
+            .map(|thread| {
+                let my_logger = Arc::clone(&my_logger);
+
+                std::thread::spawn(move || {
+                    // Long enough to need several writes if the line were written in pieces.
+                    let pattern = thread.to_string().repeat(3000);
+
+                    for _ in 0..LINES {
+                        my_logger.log(&pattern);
+                    }
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        return;
+    }
+
+    let stdout = child_stdout("concurrent_lines_are_not_torn");
+    let mut counts = [0; THREADS];
+
+    for line in stdout
+        .lines()
+        .filter(|line| line.starts_with("My Logger - ["))
+    {
+        let msg = line.split_once("]: ").unwrap().1;
+        let thread = msg[..1].parse::<usize>().unwrap();
+
+        assert_eq!(msg, thread.to_string().repeat(3000), "torn line: {}", line);
+        counts[thread] += 1;
+    }
+
+    assert_eq!(counts, [LINES; THREADS]);
+}
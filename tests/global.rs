@@ -0,0 +1,105 @@
+//! The global logger, used only through `lawg::init` and the macros.
+
+use std::process::{Command, Output};
+
+use lawg::Logger;
+
+/// Runs `test_name` again in a child process with `LAWG_CHILD` set, so it starts without a global logger.
+fn child_output(test_name: &str) -> Output {
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args([test_name, "--exact", "--nocapture", "--quiet"])
+        .env("LAWG_CHILD", "1")
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
+
+#[test]
+fn macros_log_with_the_global_logger() {
+    if std::env::var_os("LAWG_CHILD").is_some() {
+        let file = std::env::temp_dir().join(format!("lawg-global-{}.txt", std::process::id()));
+
+        lawg::init(Logger::new(
+            "Global".to_string(),
+            Some(file.to_str().unwrap().to_string()),
+            true,
+        ))
+        .unwrap();
+        assert!(matches!(
+            lawg::init(Logger::new("Again".to_string(), None, true)),
+            Err(lawg::LoggerError::AlreadyInitialized)
+        ));
+
+        let id = 42;
+        lawg::log!("User {} logged in", id);
+        lawg::log_file!("User {} logged in", id);
+        lawg::error_file!("User {id} is gone");
+        lawg::warn!("{} users left", 0);
+        lawg::error!("No users");
+
+        println!("{}", std::fs::read_to_string(&file).unwrap());
+        std::fs::remove_file(&file).unwrap();
+        return;
+    }
+
+    let output = child_output("macros_log_with_the_global_logger");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    let messages = |text: &str| {
+        text.lines()
+            .filter_map(|line| {
+                // Skips the test harness's own lines.
+                let (head, msg) = line.split_once(" - [")?;
+                Some(format!("{}: {}", head, msg.split_once("]: ")?.1))
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        messages(&stdout),
+        [
+            // The console lines, then the file.
+            "Global: User 42 logged in",
+            "WARN: Global: 0 users left",
+            "Global: User 42 logged in",
+            "ERROR: Global: User 42 is gone",
+            "WARN: Global: 0 users left",
+        ]
+    );
+    assert_eq!(messages(&stderr), ["ERROR: Global: No users"]);
+}
+
+#[test]
+fn macros_fall_back_to_a_console_logger() {
+    if std::env::var_os("LAWG_CHILD").is_some() {
+        lawg::info!("Started without {}", "init");
+        return;
+    }
+
+    let output = child_output("macros_fall_back_to_a_console_logger");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let name = std::env::current_exe().unwrap();
+    let name = name.file_stem().unwrap().to_str().unwrap();
+
+    let line = stdout
+        .lines()
+        .find(|line| line.starts_with("INFO: "))
+        .unwrap();
+    assert!(
+        line.starts_with(&format!("INFO: {} - [\"", name)),
+        "{}",
+        line
+    );
+    assert!(line.ends_with("\"]: Started without init"), "{}", line);
+
+    if cfg!(not(feature = "chrono")) {
+        assert!(line.contains(" UTC\"]: "), "{}", line);
+    }
+}